@@ -0,0 +1,349 @@
+//! Derive macros for the command and input enums of `rs-rts`.
+//!
+//! The generated code refers to the game's `crate::core` module, so these derives
+//! are only meant to be used from inside the game crate.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Fields, Ident, LitInt, LitStr, Type};
+
+/// Derives `Display`, `FromString` and `SerializeEnum` for an enum.
+///
+/// Every variant is written with a name chosen by the `command` attributes:
+///
+/// - `#[command(namespace = "camera")]` on the enum names `MoveForward` `camera.moveForward`;
+/// - `#[command(prefix = "Mouse")]` on the enum names `Left` `MouseLeft`;
+/// - without either, the variant is named as declared;
+/// - `#[command(name = "0")]` on a variant overrides its name.
+///
+/// Variants may carry a single `CommandPayload`, as `Zoom(f32)` or `Move { target: Vec3 }`,
+/// written `name(payload)`; the payload writes itself and may be omitted when it is
+/// the default one.
+#[proc_macro_derive(SerializeEnum, attributes(command))]
+pub fn derive_serialize_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, serialize_enum).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Derives `Command` and `CommandType` for an enum that also derives `SerializeEnum`,
+/// so the registry can parse it. The enum needs a `#[command(namespace = "...", id = ...)]`,
+/// the id identifying the command type on the wire.
+///
+/// Every variant needs an explicit discriminant, which is its wire id; the enum is
+/// expected to be `#[repr(u16)]`, with one bit per variant so it fits a `CommandSet`.
+/// Payload variants are scaled by the analog axis in `with_axis`, merged with
+/// `CommandPayload::merge`, and registered with their default payload.
+#[proc_macro_derive(Command, attributes(command))]
+pub fn derive_command(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, command).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+struct EnumAttributes {
+    namespace: Option<String>,
+    prefix: Option<String>,
+    id: Option<LitInt>,
+}
+
+struct VariantInfo {
+    ident: Ident,
+    name: String,
+    /// The name of the payload field, for variants with a named one.
+    field: Option<Ident>,
+    payload: Option<Type>,
+    discriminant: Option<Expr>,
+}
+
+impl VariantInfo {
+    /// The variant holding `value` as its payload, as a pattern or an expression.
+    fn with_payload(&self, ty: &Ident, value: TokenStream2) -> TokenStream2 {
+        let ident = &self.ident;
+        match (&self.field, &self.payload) {
+            (Some(field), _) => quote! { #ty::#ident { #field: #value } },
+            (None, Some(_))  => quote! { #ty::#ident(#value) },
+            (None, None)     => quote! { #ty::#ident },
+        }
+    }
+}
+
+fn expand(
+    input: &DeriveInput,
+    derive: fn(&DeriveInput, &EnumAttributes, &[VariantInfo]) -> syn::Result<TokenStream2>,
+) -> syn::Result<TokenStream2> {
+    let attributes = enum_attributes(input)?;
+    let variants = variants(input, &attributes)?;
+    derive(input, &attributes, &variants)
+}
+
+fn enum_attributes(input: &DeriveInput) -> syn::Result<EnumAttributes> {
+    let mut attributes = EnumAttributes { namespace: None, prefix: None, id: None };
+    for attribute in input.attrs.iter().filter(|attribute| attribute.path().is_ident("command")) {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("namespace") {
+                attributes.namespace = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("prefix") {
+                attributes.prefix = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("id") {
+                attributes.id = Some(meta.value()?.parse::<LitInt>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `namespace`, `prefix` or `id`"))
+            }
+        })?;
+    }
+
+    if attributes.namespace.is_some() && attributes.prefix.is_some() {
+        return Err(syn::Error::new_spanned(&input.ident, "`namespace` and `prefix` are exclusive"));
+    }
+    Ok(attributes)
+}
+
+fn variants(input: &DeriveInput, attributes: &EnumAttributes) -> syn::Result<Vec<VariantInfo>> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(&input.ident, "only enums can be derived"));
+    };
+
+    data.variants
+        .iter()
+        .map(|variant| {
+            let (field, payload) = match &variant.fields {
+                Fields::Unit => (None, None),
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => (None, Some(fields.unnamed[0].ty.clone())),
+                Fields::Named(fields) if fields.named.len() == 1 => {
+                    (fields.named[0].ident.clone(), Some(fields.named[0].ty.clone()))
+                }
+                _ => return Err(syn::Error::new_spanned(variant, "variants carry at most one payload")),
+            };
+
+            let mut name = None;
+            for attribute in variant.attrs.iter().filter(|attribute| attribute.path().is_ident("command")) {
+                attribute.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("name") {
+                        name = Some(meta.value()?.parse::<LitStr>()?.value());
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `name`"))
+                    }
+                })?;
+            }
+
+            let name = name.unwrap_or_else(|| variant_name(&variant.ident, attributes));
+            let discriminant = variant.discriminant.as_ref().map(|(_, discriminant)| discriminant.clone());
+            Ok(VariantInfo { ident: variant.ident.clone(), name, field, payload, discriminant })
+        })
+        .collect()
+}
+
+fn variant_name(ident: &Ident, attributes: &EnumAttributes) -> String {
+    let ident = ident.to_string();
+    match (&attributes.namespace, &attributes.prefix) {
+        (Some(namespace), _) => {
+            let mut chars = ident.chars();
+            let first = chars.next().map(|c| c.to_ascii_lowercase()).into_iter();
+            format!("{}.{}", namespace, first.chain(chars).collect::<String>())
+        }
+        (None, Some(prefix)) => format!("{}{}", prefix, ident),
+        (None, None) => ident,
+    }
+}
+
+fn serialize_enum(input: &DeriveInput, _: &EnumAttributes, variants: &[VariantInfo]) -> syn::Result<TokenStream2> {
+    let ty = &input.ident;
+
+    let display_arms = variants.iter().map(|variant| {
+        let name = &variant.name;
+        let pattern = variant.with_payload(ty, quote! { payload });
+        match variant.payload {
+            Some(_) => quote! { #pattern => crate::core::command::CommandPayload::write_payload(payload, f, #name), },
+            None => quote! { #pattern => f.write_str(#name), },
+        }
+    });
+
+    let payload_arms = variants.iter().filter(|variant| variant.payload.is_some()).map(|variant| {
+        let name = &variant.name;
+        let value = variant.with_payload(ty, quote! { payload });
+        quote! {
+            #name => crate::core::command::CommandPayload::parse_payload(payload).map(|payload| #value),
+        }
+    });
+    let unit_arms = variants.iter().filter(|variant| variant.payload.is_none()).map(|VariantInfo { ident, name, .. }| {
+        quote! { #name => Some(#ty::#ident), }
+    });
+
+    let from_string = if variants.iter().any(|variant| variant.payload.is_some()) {
+        quote! {
+            let (name, payload) = crate::core::command::split_payload(s)?;
+            match name {
+                #(#payload_arms)*
+                _ if payload.is_some() => None,
+                #(#unit_arms)*
+                _ => None,
+            }
+        }
+    } else {
+        quote! {
+            match s {
+                #(#unit_arms)*
+                _ => None,
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl ::std::fmt::Display for #ty {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                match self {
+                    #(#display_arms)*
+                }
+            }
+        }
+
+        impl crate::core::FromString for #ty {
+            fn from_string(s: &str) -> Option<Self> {
+                #from_string
+            }
+        }
+
+        impl crate::core::SerializeEnum for #ty { }
+    })
+}
+
+fn command(input: &DeriveInput, attributes: &EnumAttributes, variants: &[VariantInfo]) -> syn::Result<TokenStream2> {
+    let ty = &input.ident;
+    let Some(namespace) = &attributes.namespace else {
+        return Err(syn::Error::new_spanned(ty, "commands need a `#[command(namespace = \"...\")]`"));
+    };
+    let Some(type_id) = &attributes.id else {
+        return Err(syn::Error::new_spanned(ty, "commands need a `#[command(id = ...)]`"));
+    };
+    let ids = variants
+        .iter()
+        .map(|variant| {
+            variant.discriminant.as_ref().ok_or_else(|| {
+                syn::Error::new_spanned(&variant.ident, "command variants need an explicit discriminant as their wire id")
+            })
+        })
+        .collect::<syn::Result<Vec<&Expr>>>()?;
+
+    let with_axis = if variants.iter().any(|variant| variant.payload.is_some()) {
+        let arms = variants.iter().filter(|variant| variant.payload.is_some()).map(|variant| {
+            let pattern = variant.with_payload(ty, quote! { payload });
+            let value = variant.with_payload(ty, quote! { crate::core::command::CommandPayload::with_axis(payload, axis) });
+            quote! { #pattern => #value, }
+        });
+        let rest = variants.iter().any(|variant| variant.payload.is_none()).then(|| quote! { command => command, });
+        let merge_arms = variants.iter().filter(|variant| variant.payload.is_some()).map(|variant| {
+            let pattern = variant.with_payload(ty, quote! { payload });
+            let other = variant.with_payload(ty, quote! { other });
+            let value = variant.with_payload(ty, quote! { crate::core::command::CommandPayload::merge(payload, other) });
+            quote! { (#pattern, #other) => #value, }
+        });
+        quote! {
+            fn with_axis(self, axis: ::bevy::math::Vec2) -> Self {
+                match self {
+                    #(#arms)*
+                    #rest
+                }
+            }
+
+            fn merge(self, other: Self) -> Self {
+                match (self, other) {
+                    #(#merge_arms)*
+                    (command, _) => command,
+                }
+            }
+        }
+    } else {
+        TokenStream2::new()
+    };
+
+    let id_arms = variants.iter().zip(&ids).map(|(variant, id)| {
+        let pattern = variant.with_payload(ty, quote! { _ });
+        quote! { #pattern => #id, }
+    });
+
+    let name_arms = variants.iter().map(|variant| {
+        let name = &variant.name;
+        let pattern = variant.with_payload(ty, quote! { _ });
+        quote! { #pattern => #name, }
+    });
+
+    let encode_payload = if variants.iter().any(|variant| variant.payload.is_some()) {
+        let arms = variants.iter().filter(|variant| variant.payload.is_some()).map(|variant| {
+            let pattern = variant.with_payload(ty, quote! { payload });
+            quote! { #pattern => crate::core::command::CommandPayload::encode_payload(payload, writer), }
+        });
+        let rest = variants.iter().any(|variant| variant.payload.is_none()).then(|| quote! { _ => {} });
+        quote! {
+            match self {
+                #(#arms)*
+                #rest
+            }
+        }
+    } else {
+        TokenStream2::new()
+    };
+
+    let decode_arms = variants.iter().zip(&ids).map(|(variant, id)| {
+        let value = match &variant.payload {
+            Some(payload) => variant.with_payload(ty, quote! {
+                <#payload as crate::core::command::CommandPayload>::decode_payload(reader)?
+            }),
+            None => variant.with_payload(ty, TokenStream2::new()),
+        };
+        quote! { id if id == u64::from((#id) as u16) => Ok(#value), }
+    });
+
+    let values = variants.iter().map(|variant| match &variant.payload {
+        Some(payload) => variant.with_payload(ty, quote! {
+            <#payload as crate::core::command::CommandPayload>::default_payload()
+        }),
+        None => variant.with_payload(ty, TokenStream2::new()),
+    });
+
+    Ok(quote! {
+        impl crate::core::command::Command for #ty {
+            fn as_any(&self) -> &dyn ::std::any::Any { self }
+
+            #with_axis
+
+            fn id(&self) -> u16 {
+                match self {
+                    #(#id_arms)*
+                }
+            }
+
+            fn name(&self) -> &'static str {
+                match self {
+                    #(#name_arms)*
+                }
+            }
+
+            fn encode(&self, writer: &mut crate::core::command::wire::WireWriter) {
+                writer.write_varint(u64::from(self.id()));
+                #encode_payload
+            }
+
+            fn decode(
+                reader: &mut crate::core::command::wire::WireReader,
+            ) -> Result<Self, crate::core::command::wire::WireError> {
+                match reader.read_varint()? {
+                    #(#decode_arms)*
+                    id => Err(crate::core::command::wire::WireError::UnknownCommand { namespace: #namespace, id }),
+                }
+            }
+        }
+
+        impl crate::core::command::registry::CommandType for #ty {
+            const NAMESPACE: &'static str = #namespace;
+            const ID: u16 = #type_id;
+
+            fn variants() -> Vec<Self> {
+                vec![#(#values),*]
+            }
+        }
+    })
+}
@@ -1,12 +1,20 @@
-use crate::core::command::{Command, CommandHandler};
-use crate::core::{FromString, SerializeEnum};
-use bevy::math::Vec3;
-use bevy::prelude::{Camera3d, Query, Transform, With};
-use std::any::Any;
-use std::fmt::Display;
+use crate::core::command::set::CommandSet;
+use crate::core::command::{downcast_commands, Command, CommandAppExt, CommandDispatch, CommandHandler, CommandDispatchSet, CommandSystem, ExtCommandHandler};
+use crate::core::input::context::{InputContext, InputContexts};
+use crate::core::input::{InputPlugin, InputSet};
+use crate::core::SerializeEnum;
+use bevy::input::ButtonInput;
+use bevy::math::{Quat, Vec2, Vec3};
+use bevy::prelude::{App, Camera3d, IntoScheduleConfigs, MouseButton, Plugin, PreUpdate, Query, Res, ResMut, Resource, Time, Transform, Update, Window, With};
+use bevy::window::{CursorGrabMode, PrimaryWindow};
 
+/// Camera movements. The digital commands act while their binding is held; the
+/// `Rotate`, `Pan` and `Zoom` commands carry an axis value applied once, where `1.0`
+/// moves the camera as far as holding the matching digital command for one second.
 #[repr(u16)]
-enum CameraCommand {
+#[derive(Clone, Copy, Debug, PartialEq, SerializeEnum, Command)]
+#[command(namespace = "camera", id = 1)]
+pub enum CameraCommand {
     MoveForward  = 0x1 << 0,
     MoveBackward = 0x1 << 1,
     MoveUp       = 0x1 << 8,
@@ -17,123 +25,453 @@ enum CameraCommand {
     RotateRight  = 0x1 << 5,
     ZoomIn       = 0x1 << 6,
     ZoomOut      = 0x1 << 7,
+    Rotate(f32)  = 0x1 << 10,
+    Pan(Vec2)    = 0x1 << 11,
+    Zoom(f32)    = 0x1 << 12,
 }
 
-impl Display for CameraCommand {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let str = match self {
-            CameraCommand::MoveForward  => "camera.moveForward",
-            CameraCommand::MoveBackward => "camera.moveBackward",
-            CameraCommand::MoveUp       => "camera.moveUp",
-            CameraCommand::MoveDown     => "camera.moveDown",
-            CameraCommand::MoveLeft     => "camera.moveLeft",
-            CameraCommand::MoveRight    => "camera.moveRight",
-            CameraCommand::RotateLeft   => "camera.rotateLeft",
-            CameraCommand::RotateRight  => "camera.rotateRight",
-            CameraCommand::ZoomIn       => "camera.zoomIn",
-            CameraCommand::ZoomOut      => "camera.zoomOut",
-        };
-        write!(f, "{}", str)
-    }
+const DEFAULT_ROTATION_SPEED: f32 = 1.5;
+const DEFAULT_ZOOM_SPEED: f32 = 10.0;
+const DEFAULT_MIN_HEIGHT: f32 = 2.0;
+const DEFAULT_MAX_HEIGHT: f32 = 40.0;
+const DEFAULT_EDGE_SCROLL_MARGIN: f32 = 50.0;
+
+/// Scrolling the camera by bringing the cursor close to an edge of the window.
+///
+/// Within `margin` pixels of an edge the camera pans away from the centre, from
+/// standing still at the inner border of the margin up to `speed` times the camera
+/// speed at the edge itself. Near a corner both edges pan, no faster than one edge
+/// alone. Nothing scrolls while the window is unfocused, the cursor is outside it,
+/// or the middle mouse button is held to rotate.
+#[derive(Clone, Copy)]
+pub struct EdgeScroll {
+    pub enabled: bool,
+    /// Width of the scrolling band along the edges, in pixels.
+    pub margin: f32,
+    /// Speed at the very edge, relative to the camera speed.
+    pub speed: f32,
+    /// Confines the cursor to the window while it is focused, so it cannot slip onto
+    /// another monitor instead of scrolling.
+    pub lock_cursor: bool,
 }
 
-impl FromString for CameraCommand {
-    fn from_string(s: &str) -> Option<Self> {
-        match s {
-            "CameraMoveForward"  => Some(CameraCommand::MoveForward),
-            "CameraMoveBackward" => Some(CameraCommand::MoveBackward),
-            "CameraMoveUp"       => Some(CameraCommand::MoveUp),
-            "CameraMoveDown"     => Some(CameraCommand::MoveDown),
-            "CameraMoveLeft"     => Some(CameraCommand::MoveLeft),
-            "CameraMoveRight"    => Some(CameraCommand::MoveRight),
-            "CameraRotateLeft"   => Some(CameraCommand::RotateLeft),
-            "CameraRotateRight"  => Some(CameraCommand::RotateRight),
-            "CameraZoomIn"       => Some(CameraCommand::ZoomIn),
-            "CameraZoomOut"      => Some(CameraCommand::ZoomOut),
-            _ => None,
-        }
+impl Default for EdgeScroll {
+    fn default() -> Self {
+        Self { enabled: true, margin: DEFAULT_EDGE_SCROLL_MARGIN, speed: 1.0, lock_cursor: false }
     }
 }
 
-impl SerializeEnum for CameraCommand { }
+impl EdgeScroll {
+    /// The pan per second for the cursor at `position` in a window of `size`, with `x`
+    /// to the right and `y` forward.
+    pub fn pan(&self, position: Vec2, size: Vec2) -> Vec2 {
+        if !self.enabled || self.margin <= 0.0 {
+            return Vec2::ZERO;
+        }
 
-impl Command for CameraCommand {
-    fn as_any(&self) -> &dyn Any { self }
+        // 0 at the inner border of the margin, 1 on the edge
+        let ramp = |distance: f32| (1.0 - distance / self.margin).clamp(0.0, 1.0);
+        let x = ramp(size.x - position.x) - ramp(position.x);
+        // Screen coordinates grow downwards
+        let y = ramp(position.y) - ramp(size.y - position.y);
+        Vec2::new(x, y).clamp_length_max(1.0) * self.speed
+    }
 }
 
+#[derive(Resource)]
 pub struct CameraSystem {
     speed: f32,
-    forward_move : i8,
-    up_move : i8,
-    right_move : i8,
+    rotation_speed: f32,
+    zoom_speed: f32,
+    min_height: f32,
+    max_height: f32,
+    forward_move : f32,
+    up_move : f32,
+    right_move : f32,
+    rotate_move : f32,
+    zoom_move : f32,
+    pan_delta : Vec2,
+    rotate_delta : f32,
+    zoom_delta : f32,
+    center_target: Option<Vec3>,
+    edge_scroll: EdgeScroll,
 }
 
 impl CameraSystem {
-    pub fn new() -> Self {
-        Self {
-            speed: 1.0,
-            forward_move: 0,
-            up_move: 0,
-            right_move: 0,
-        }
+    pub fn with_speed(speed: f32) -> Self {
+        Self::with_speeds(speed, DEFAULT_ROTATION_SPEED, DEFAULT_ZOOM_SPEED)
     }
 
-    pub fn with_speed(speed: f32) -> Self {
+    /// `speed` is in units per second, `rotation_speed` in radians per second and
+    /// `zoom_speed` in units per second along the view ray.
+    pub fn with_speeds(speed: f32, rotation_speed: f32, zoom_speed: f32) -> Self {
         Self {
             speed,
-            forward_move: 0,
-            up_move: 0,
-            right_move: 0,
+            rotation_speed,
+            zoom_speed,
+            min_height: DEFAULT_MIN_HEIGHT,
+            max_height: DEFAULT_MAX_HEIGHT,
+            forward_move: 0.0,
+            up_move: 0.0,
+            right_move: 0.0,
+            rotate_move: 0.0,
+            zoom_move: 0.0,
+            pan_delta: Vec2::ZERO,
+            rotate_delta: 0.0,
+            zoom_delta: 0.0,
+            center_target: None,
+            edge_scroll: EdgeScroll::default(),
         }
     }
 
+    /// Limits how close to and how far from the ground the camera can get.
+    pub fn with_height_limits(mut self, min_height: f32, max_height: f32) -> Self {
+        self.min_height = min_height;
+        self.max_height = max_height.max(min_height);
+        self
+    }
+
+    pub fn with_edge_scroll(mut self, edge_scroll: EdgeScroll) -> Self {
+        self.edge_scroll = edge_scroll;
+        self
+    }
+
+    pub fn edge_scroll(&self) -> &EdgeScroll {
+        &self.edge_scroll
+    }
+
+    /// Moves the camera on its next update so it looks at the ground under `point`,
+    /// keeping its height and orientation.
+    pub fn center_on(&mut self, point: Vec3) {
+        self.center_target = Some(point);
+    }
+
     pub fn update(&mut self, dt: f32, query: &mut Query<&mut Transform, With<Camera3d>>) {
+        // Held commands move at a rate, analog deltas are applied once
+        let forward_amount = self.forward_move * dt + self.pan_delta.y;
+        let right_amount = self.right_move * dt + self.pan_delta.x;
+        let up_amount = self.up_move * dt;
+        let rotate_amount = self.rotate_move * dt + self.rotate_delta;
+        let zoom_amount = self.zoom_move * dt + self.zoom_delta;
+        let center_target = self.center_target.take();
+
         for mut transform in query.iter_mut() {
+            if let Some(target) = center_target {
+                let focus = ground_focus(&transform);
+                transform.translation += Vec3::new(target.x - focus.x, 0.0, target.z - focus.z);
+            }
+
             let forward = transform.forward();
             let right = transform.right();
 
             let forward_xz = Vec3::new(forward.x, 0.0, forward.z).normalize_or_zero();
             let right_xz = Vec3::new(right.x, 0.0, right.z).normalize_or_zero();
 
-            if self.forward_move != 0 {
-                transform.translation += forward_xz * self.forward_move as f32 * self.speed * dt;
+            if forward_amount != 0.0 {
+                transform.translation += forward_xz * forward_amount * self.speed;
+            }
+
+            if right_amount != 0.0 {
+                transform.translation += right_xz * right_amount * self.speed;
             }
 
-            if self.right_move != 0 {
-                transform.translation += right_xz * self.right_move as f32 * self.speed * dt;
+            if up_amount != 0.0 {
+                let height = transform.translation.y + up_amount * self.speed;
+                transform.translation.y = height.clamp(self.min_height, self.max_height);
             }
 
-            if self.up_move != 0 {
-                transform.translation += Vec3::new(0.0, self.up_move as f32 * self.speed * dt, 0.0);
+            if rotate_amount != 0.0 {
+                let focus = ground_focus(&transform);
+                let angle = rotate_amount * self.rotation_speed;
+                transform.rotate_around(focus, Quat::from_rotation_y(angle));
+            }
+
+            if zoom_amount != 0.0 && forward.y.abs() > f32::EPSILON {
+                // Move along the view ray, stopping where the ray leaves the allowed heights
+                let distance = zoom_amount * self.zoom_speed;
+                let height = (transform.translation.y + forward.y * distance)
+                    .clamp(self.min_height, self.max_height);
+                let distance = (height - transform.translation.y) / forward.y;
+                transform.translation += forward * distance;
             }
         }
+
+        self.pan_delta = Vec2::ZERO;
+        self.rotate_delta = 0.0;
+        self.zoom_delta = 0.0;
     }
 }
 
+/// The point of the ground plane the camera is looking at, or the camera's own
+/// position when it does not look down.
+fn ground_focus(transform: &Transform) -> Vec3 {
+    let forward = transform.forward();
+    if forward.y >= -f32::EPSILON {
+        return transform.translation;
+    }
+
+    let distance = -transform.translation.y / forward.y;
+    transform.translation + forward * distance
+}
+
 impl CommandHandler<CameraCommand> for CameraSystem {
-    fn handle_command(&mut self, commands: &Vec<CameraCommand>) {
-        let mut forward_move : i8 = 0;
-        let mut up_move : i8 = 0;
-        let mut right_move : i8 = 0;
+    fn handle_commands(&mut self, commands: &CommandSet<CameraCommand>) {
+        let mut forward_move : f32 = 0.0;
+        let mut up_move : f32 = 0.0;
+        let mut right_move : f32 = 0.0;
+        let mut rotate_move : f32 = 0.0;
+        let mut zoom_move : f32 = 0.0;
 
         for command in commands {
             match command {
-                CameraCommand::MoveForward  => forward_move += 1,
-                CameraCommand::MoveBackward => forward_move -= 1,
-                CameraCommand::MoveUp       => up_move += 1,
-                CameraCommand::MoveDown     => up_move -= 1,
-                CameraCommand::MoveLeft     => right_move -= 1,
-                CameraCommand::MoveRight    => right_move += 1,
-                // CameraCommand::RotateLeft   => ,
-                // CameraCommand::RotateRight  => ,
-                // CameraCommand::ZoomIn       => ,
-                // CameraCommand::ZoomOut      => ,
-                _ => (),
+                CameraCommand::MoveForward  => forward_move += 1.0,
+                CameraCommand::MoveBackward => forward_move -= 1.0,
+                CameraCommand::MoveUp       => up_move += 1.0,
+                CameraCommand::MoveDown     => up_move -= 1.0,
+                CameraCommand::MoveLeft     => right_move -= 1.0,
+                CameraCommand::MoveRight    => right_move += 1.0,
+                CameraCommand::RotateLeft   => rotate_move += 1.0,
+                CameraCommand::RotateRight  => rotate_move -= 1.0,
+                CameraCommand::ZoomIn       => zoom_move += 1.0,
+                CameraCommand::ZoomOut      => zoom_move -= 1.0,
+                CameraCommand::Rotate(axis) => self.rotate_delta += axis,
+                CameraCommand::Pan(axis)    => self.pan_delta += *axis,
+                CameraCommand::Zoom(axis)   => self.zoom_delta += axis,
             }
         }
 
-        self.forward_move = forward_move.clamp(-1, 1);
-        self.up_move = up_move.clamp(-1, 1);
-        self.right_move = right_move.clamp(-1, 1);
+        self.forward_move = forward_move.clamp(-1.0, 1.0);
+        self.up_move = up_move.clamp(-1.0, 1.0);
+        self.right_move = right_move.clamp(-1.0, 1.0);
+        self.rotate_move = rotate_move.clamp(-1.0, 1.0);
+        self.zoom_move = zoom_move.clamp(-1.0, 1.0);
+    }
+}
+
+impl ExtCommandHandler for CameraSystem {
+    fn handle_ext_commands(&mut self, commands: &[&dyn Command]) {
+        self.handle_commands(&downcast_commands::<CameraCommand>(commands));
+    }
+}
+
+/// Drives every `Camera3d` from the `CameraCommand` bindings of the active `BindingProfile`.
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(InputPlugin::<CameraCommand>::default())
+            .register_command::<CameraCommand>()
+            .insert_resource(CameraSystem::with_speed(5.0))
+            .add_command_handler::<CameraSystem>("camera.*")
+            .add_systems(PreUpdate, edge_scroll_camera.in_set(InputSet))
+            .add_systems(Update, update_camera.after(CommandDispatchSet));
+    }
+}
+
+fn update_camera(
+    time: Res<Time>,
+    mut camera: ResMut<CameraSystem>,
+    mut query: Query<&mut Transform, With<Camera3d>>,
+) {
+    camera.update(time.delta_secs(), &mut query);
+}
+
+/// Pans the camera while the cursor is near an edge of the primary window, and
+/// confines the cursor to the window if the `EdgeScroll` settings ask for it. The
+/// pan belongs to `Gameplay`, so it stops while a context above takes the pointer.
+fn edge_scroll_camera(
+    time: Res<Time>,
+    camera: Res<CameraSystem>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    contexts: Res<InputContexts>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut queue: ResMut<CommandSystem>,
+) {
+    let Ok(mut window) = windows.single_mut() else {
+        return;
+    };
+
+    let edge_scroll = camera.edge_scroll();
+    let grab_mode = match edge_scroll.lock_cursor && window.focused {
+        true  => CursorGrabMode::Confined,
+        false => CursorGrabMode::None,
+    };
+    if window.cursor_options.grab_mode != grab_mode {
+        window.cursor_options.grab_mode = grab_mode;
+    }
+
+    if !window.focused || buttons.pressed(MouseButton::Middle) || !contexts.pointer_reaches(InputContext::Gameplay) {
+        return;
+    }
+
+    if let Some(position) = window.cursor_position() {
+        let pan = edge_scroll.pan(position, window.size());
+        if pan != Vec2::ZERO {
+            queue.push_command(CameraCommand::Pan(pan * time.delta_secs()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::TestApp;
+    use bevy::prelude::{KeyCode, MouseButton};
+
+    const START: Vec3 = Vec3::new(0.0, 10.0, 10.0);
+
+    fn app() -> TestApp {
+        let mut app = TestApp::new(CameraPlugin);
+        app.spawn_camera(START);
+        app
+    }
+
+    #[test]
+    fn held_key_moves_at_camera_speed() {
+        let mut app = app();
+        app.press(KeyCode::KeyW);
+        app.run(60);
+
+        assert_eq!(app.last_commands(), ["camera.moveForward"]);
+        let moved = app.camera_transform().translation - START;
+        assert!((moved.length() - 5.0).abs() < 1e-3, "moved {}", moved);
+        assert!(moved.z < 0.0 && moved.x.abs() < 1e-4 && moved.y.abs() < 1e-4);
+
+        app.release(KeyCode::KeyW);
+        app.update();
+        let stopped = app.camera_transform().translation;
+        app.run(10);
+
+        assert!(app.last_commands().is_empty());
+        assert_eq!(app.camera_transform().translation, stopped);
+    }
+
+    #[test]
+    fn zoom_stops_at_height_limits() {
+        let mut app = app();
+        app.press(KeyCode::KeyX);
+        app.run(600);
+        assert!((app.camera_transform().translation.y - DEFAULT_MAX_HEIGHT).abs() < 1e-3);
+
+        app.release(KeyCode::KeyX);
+        app.press(KeyCode::KeyZ);
+        app.run(600);
+        assert!((app.camera_transform().translation.y - DEFAULT_MIN_HEIGHT).abs() < 1e-3);
+    }
+
+    #[test]
+    fn wheel_zooms_once_per_turn() {
+        let mut app = app();
+        app.scroll(2.0);
+        app.update();
+
+        assert_eq!(app.last_commands(), ["camera.zoom(0.2)"]);
+        let zoomed = app.camera_transform().translation;
+        assert!((zoomed.distance(START) - 0.2 * DEFAULT_ZOOM_SPEED).abs() < 1e-3);
+        assert!(zoomed.y < START.y);
+
+        app.run(5);
+        assert!(app.last_commands().is_empty());
+        assert_eq!(app.camera_transform().translation, zoomed);
+    }
+
+    #[test]
+    fn rotation_orbits_the_ground_focus() {
+        let mut app = app();
+        let focus = ground_focus(&app.camera_transform());
+        app.press(KeyCode::KeyQ);
+        app.run(30);
+
+        let transform = app.camera_transform();
+        assert!(transform.translation.distance(START) > 1.0);
+        assert!((transform.translation.distance(focus) - START.distance(focus)).abs() < 1e-3);
+        assert!(ground_focus(&transform).distance(focus) < 1e-3);
+    }
+
+    fn edge_app(edge_scroll: EdgeScroll) -> TestApp {
+        let mut app = TestApp::new(CameraPlugin);
+        app.world_mut().resource_mut::<CameraSystem>().edge_scroll = edge_scroll;
+        app.spawn_window(800.0, 600.0);
+        app.spawn_camera(START);
+        app
+    }
+
+    #[test]
+    fn edge_scrolling_ramps_up_towards_the_edge() {
+        let mut app = edge_app(EdgeScroll::default());
+        app.set_cursor(Some(Vec2::new(10.0, 300.0)));
+        app.run(60);
+
+        // 10 pixels from the edge of a 50 pixel margin
+        let moved = app.camera_transform().translation - START;
+        assert!((moved.x + 0.8 * 5.0).abs() < 1e-3, "moved {}", moved);
+        assert!(moved.z.abs() < 1e-4);
+
+        let start = app.camera_transform().translation;
+        app.set_cursor(Some(Vec2::new(400.0, 575.0)));
+        app.run(60);
+        let moved = app.camera_transform().translation - start;
+        assert!((moved.z - 0.5 * 5.0).abs() < 1e-3, "moved {}", moved);
+    }
+
+    #[test]
+    fn edge_scrolling_is_no_faster_in_corners() {
+        let mut app = edge_app(EdgeScroll { speed: 2.0, ..EdgeScroll::default() });
+        app.set_cursor(Some(Vec2::new(799.0, 1.0)));
+        app.run(60);
+
+        let moved = app.camera_transform().translation - START;
+        assert!((moved.length() - 2.0 * 5.0).abs() < 1e-3, "moved {}", moved);
+        assert!((moved.x + moved.z).abs() < 1e-3 && moved.x > 0.0);
+    }
+
+    #[test]
+    fn edge_scrolling_stops_away_from_the_window() {
+        let mut app = edge_app(EdgeScroll::default());
+        app.set_cursor(Some(Vec2::new(400.0, 300.0)));
+        app.run(10);
+        app.set_cursor(None);
+        app.run(10);
+
+        app.set_cursor(Some(Vec2::new(10.0, 300.0)));
+        app.press_mouse(MouseButton::Middle);
+        app.run(10);
+        app.release_mouse(MouseButton::Middle);
+        app.window_mut().focused = false;
+        app.run(10);
+
+        assert!(app.all_commands().iter().all(|frame| frame.is_empty()));
+        assert_eq!(app.camera_transform().translation, START);
+
+        app.world_mut().resource_mut::<CameraSystem>().edge_scroll.enabled = false;
+        app.window_mut().focused = true;
+        app.run(10);
+        assert_eq!(app.camera_transform().translation, START);
+    }
+
+    #[test]
+    fn edge_scrolling_stops_under_a_menu() {
+        let mut app = edge_app(EdgeScroll::default());
+        app.world_mut().resource_mut::<InputContexts>().push(InputContext::Menu);
+        app.set_cursor(Some(Vec2::new(10.0, 300.0)));
+        app.run(10);
+        assert!(app.all_commands().iter().all(|frame| frame.is_empty()));
+        assert_eq!(app.camera_transform().translation, START);
+
+        app.world_mut().resource_mut::<InputContexts>().push(InputContext::Chat);
+        app.world_mut().resource_mut::<InputContexts>().remove(InputContext::Menu);
+        app.run(10);
+        assert!(app.camera_transform().translation.x < START.x);
+    }
+
+    #[test]
+    fn locks_the_cursor_while_focused() {
+        let mut app = edge_app(EdgeScroll { lock_cursor: true, ..EdgeScroll::default() });
+        app.update();
+        assert_eq!(app.window_mut().cursor_options.grab_mode, CursorGrabMode::Confined);
+
+        app.window_mut().focused = false;
+        app.update();
+        assert_eq!(app.window_mut().cursor_options.grab_mode, CursorGrabMode::None);
     }
 }
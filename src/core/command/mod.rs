@@ -0,0 +1,568 @@
+use crate::core::command::registry::{CommandRegistry, CommandType};
+use crate::core::command::replay::{replay_commands, save_recording_on_exit, CommandRecorder};
+use crate::core::command::set::CommandSet;
+use crate::core::command::wire::{WireError, WireReader, WireWriter};
+use crate::core::{FromString, SerializeEnum};
+use bevy::math::{Vec2, Vec3};
+use bevy::prelude::{App, Entity, IntoScheduleConfigs, Last, Mut, Plugin, Resource, SystemSet, Update, World};
+use std::fmt;
+use std::fmt::Formatter;
+
+pub mod registry;
+pub mod replay;
+pub mod set;
+pub mod wire;
+
+pub use rs_rts_macros::Command;
+
+pub static PAYLOAD_OPEN: char = '(';
+pub static PAYLOAD_CLOSE: char = ')';
+static PAYLOAD_SEPARATOR: &str = ", ";
+static POINT_SEPARATOR: &str = "; ";
+pub static NAMESPACE_SEPARATOR: char = '.';
+static FILTER_WILDCARD: &str = "*";
+
+pub trait Command: SerializeEnum + Send + Sync + 'static {
+    fn as_any(&self) -> &dyn std::any::Any;
+
+    /// Scales the payload of commands driven by an analog axis (mouse motion, wheel,
+    /// sticks) by `axis`. Digital commands are returned unchanged.
+    fn with_axis(self, _axis: Vec2) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
+
+    /// Combines two commands of the same variant into one, as a `CommandSet` does
+    /// when a variant is inserted twice, with `CommandPayload::merge`.
+    fn merge(self, _other: Self) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
+
+    /// Stable identifier of the variant, unique within its command type. It is part
+    /// of the wire format and must not change once released.
+    fn id(&self) -> u16;
+
+    /// The name the variant is written with, without its payload, as `camera.zoom`.
+    fn name(&self) -> &'static str;
+
+    /// Writes the variant id followed by the payload, if any.
+    fn encode(&self, writer: &mut WireWriter);
+
+    fn decode(reader: &mut WireReader) -> Result<Self, WireError>
+    where
+        Self: Sized;
+}
+
+/// Splits `name(payload)` into its name and payload. A bare `name` has no payload.
+pub fn split_payload(s: &str) -> Option<(&str, Option<&str>)> {
+    match s.split_once(PAYLOAD_OPEN) {
+        Some((name, rest)) => rest.strip_suffix(PAYLOAD_CLOSE).map(|payload| (name, Some(payload))),
+        None => Some((s, None)),
+    }
+}
+
+/// Parses a one-dimensional axis payload; a missing payload means `1`.
+pub fn parse_axis(payload: Option<&str>) -> Option<f32> {
+    match payload {
+        Some(payload) => payload.trim().parse().ok(),
+        None => Some(1.0),
+    }
+}
+
+/// Parses a two-dimensional axis payload written `x, y`; a missing payload means `1, 1`.
+pub fn parse_axis2(payload: Option<&str>) -> Option<Vec2> {
+    match payload {
+        Some(payload) => {
+            let (x, y) = payload.split_once(PAYLOAD_SEPARATOR.trim())?;
+            Some(Vec2::new(x.trim().parse().ok()?, y.trim().parse().ok()?))
+        }
+        None => Some(Vec2::ONE),
+    }
+}
+
+/// Writes `name`, followed by the axis payload unless it is the default `1`.
+pub fn write_axis(f: &mut Formatter<'_>, name: &str, axis: f32) -> fmt::Result {
+    if axis == 1.0 {
+        write!(f, "{}", name)
+    } else {
+        write!(f, "{}{}{}{}", name, PAYLOAD_OPEN, axis, PAYLOAD_CLOSE)
+    }
+}
+
+/// Writes `name`, followed by the axis payload unless it is the default `1, 1`.
+pub fn write_axis2(f: &mut Formatter<'_>, name: &str, axis: Vec2) -> fmt::Result {
+    if axis == Vec2::ONE {
+        write!(f, "{}", name)
+    } else {
+        write!(f, "{}{}{}{}{}{}", name, PAYLOAD_OPEN, axis.x, PAYLOAD_SEPARATOR, axis.y, PAYLOAD_CLOSE)
+    }
+}
+
+fn parse_point(s: &str) -> Option<Vec3> {
+    let mut coordinates = s.split(PAYLOAD_SEPARATOR.trim()).map(|coordinate| coordinate.trim().parse().ok());
+    let point = Vec3::new(coordinates.next()??, coordinates.next()??, coordinates.next()??);
+    coordinates.next().is_none().then_some(point)
+}
+
+fn write_point(f: &mut Formatter<'_>, point: Vec3) -> fmt::Result {
+    write!(f, "{}{}{}{}{}", point.x, PAYLOAD_SEPARATOR, point.y, PAYLOAD_SEPARATOR, point.z)
+}
+
+/// A value carried by a command variant, written between parentheses after its name.
+pub trait CommandPayload: Sized {
+    /// The payload of a command written without one.
+    fn default_payload() -> Self;
+
+    fn parse_payload(payload: Option<&str>) -> Option<Self>;
+
+    /// Writes `name`, followed by the payload unless it is the default one.
+    fn write_payload(&self, f: &mut Formatter<'_>, name: &str) -> fmt::Result;
+
+    /// Scales the payload by the analog axis driving the command.
+    fn with_axis(self, _axis: Vec2) -> Self {
+        self
+    }
+
+    /// Combines the payloads of two commands of the same variant.
+    fn merge(self, other: Self) -> Self;
+
+    fn encode_payload(&self, writer: &mut WireWriter);
+
+    fn decode_payload(reader: &mut WireReader) -> Result<Self, WireError>;
+}
+
+impl CommandPayload for f32 {
+    fn default_payload() -> Self {
+        1.0
+    }
+
+    fn parse_payload(payload: Option<&str>) -> Option<Self> {
+        parse_axis(payload)
+    }
+
+    fn write_payload(&self, f: &mut Formatter<'_>, name: &str) -> fmt::Result {
+        write_axis(f, name, *self)
+    }
+
+    fn with_axis(self, axis: Vec2) -> Self {
+        self * axis.x
+    }
+
+    fn merge(self, other: Self) -> Self {
+        self + other
+    }
+
+    fn encode_payload(&self, writer: &mut WireWriter) {
+        writer.write_f32(*self);
+    }
+
+    fn decode_payload(reader: &mut WireReader) -> Result<Self, WireError> {
+        reader.read_f32()
+    }
+}
+
+impl CommandPayload for Vec2 {
+    fn default_payload() -> Self {
+        Vec2::ONE
+    }
+
+    fn parse_payload(payload: Option<&str>) -> Option<Self> {
+        parse_axis2(payload)
+    }
+
+    fn write_payload(&self, f: &mut Formatter<'_>, name: &str) -> fmt::Result {
+        write_axis2(f, name, *self)
+    }
+
+    fn with_axis(self, axis: Vec2) -> Self {
+        self * axis
+    }
+
+    fn merge(self, other: Self) -> Self {
+        self + other
+    }
+
+    fn encode_payload(&self, writer: &mut WireWriter) {
+        writer.write_f32(self.x);
+        writer.write_f32(self.y);
+    }
+
+    fn decode_payload(reader: &mut WireReader) -> Result<Self, WireError> {
+        Ok(Vec2::new(reader.read_f32()?, reader.read_f32()?))
+    }
+}
+
+/// A slot number, such as the control group of `group.recall(1)`. It is always
+/// written, and merging keeps the last command's payload.
+impl CommandPayload for u8 {
+    fn default_payload() -> Self {
+        0
+    }
+
+    fn parse_payload(payload: Option<&str>) -> Option<Self> {
+        payload?.trim().parse().ok()
+    }
+
+    fn write_payload(&self, f: &mut Formatter<'_>, name: &str) -> fmt::Result {
+        write!(f, "{}{}{}{}", name, PAYLOAD_OPEN, self, PAYLOAD_CLOSE)
+    }
+
+    fn merge(self, other: Self) -> Self {
+        other
+    }
+
+    fn encode_payload(&self, writer: &mut WireWriter) {
+        writer.write_u8(*self);
+    }
+
+    fn decode_payload(reader: &mut WireReader) -> Result<Self, WireError> {
+        reader.read_u8()
+    }
+}
+
+/// A point of the world, such as the target of `unit.move(4, 0, -2)`. It is always
+/// written, and merging keeps the last command's payload.
+impl CommandPayload for Vec3 {
+    fn default_payload() -> Self {
+        Vec3::ZERO
+    }
+
+    fn parse_payload(payload: Option<&str>) -> Option<Self> {
+        parse_point(payload?)
+    }
+
+    fn write_payload(&self, f: &mut Formatter<'_>, name: &str) -> fmt::Result {
+        write!(f, "{}{}", name, PAYLOAD_OPEN)?;
+        write_point(f, *self)?;
+        write!(f, "{}", PAYLOAD_CLOSE)
+    }
+
+    fn merge(self, other: Self) -> Self {
+        other
+    }
+
+    fn encode_payload(&self, writer: &mut WireWriter) {
+        writer.write_f32(self.x);
+        writer.write_f32(self.y);
+        writer.write_f32(self.z);
+    }
+
+    fn decode_payload(reader: &mut WireReader) -> Result<Self, WireError> {
+        Ok(Vec3::new(reader.read_f32()?, reader.read_f32()?, reader.read_f32()?))
+    }
+}
+
+/// A list of points, such as the waypoints of `unit.patrol(0, 0, 0; 4, 0, 0)`.
+impl CommandPayload for Vec<Vec3> {
+    fn default_payload() -> Self {
+        Vec::new()
+    }
+
+    fn parse_payload(payload: Option<&str>) -> Option<Self> {
+        match payload?.trim() {
+            "" => Some(Vec::new()),
+            points => points.split(POINT_SEPARATOR.trim()).map(parse_point).collect(),
+        }
+    }
+
+    fn write_payload(&self, f: &mut Formatter<'_>, name: &str) -> fmt::Result {
+        write!(f, "{}{}", name, PAYLOAD_OPEN)?;
+        for (index, point) in self.iter().enumerate() {
+            if index > 0 {
+                write!(f, "{}", POINT_SEPARATOR)?;
+            }
+            write_point(f, *point)?;
+        }
+        write!(f, "{}", PAYLOAD_CLOSE)
+    }
+
+    fn merge(self, other: Self) -> Self {
+        other
+    }
+
+    fn encode_payload(&self, writer: &mut WireWriter) {
+        writer.write_varint(self.len() as u64);
+        self.iter().for_each(|point| point.encode_payload(writer));
+    }
+
+    fn decode_payload(reader: &mut WireReader) -> Result<Self, WireError> {
+        let count = reader.read_varint()?;
+        // Every point takes 12 bytes, so longer lists cannot be in the packet
+        if count > (reader.remaining() / 12) as u64 {
+            return Err(WireError::UnexpectedEnd);
+        }
+        (0..count).map(|_| Vec3::decode_payload(reader)).collect()
+    }
+}
+
+/// An entity, written as its bits. Entities are only meaningful within the world
+/// that spawned them.
+impl CommandPayload for Entity {
+    fn default_payload() -> Self {
+        Entity::PLACEHOLDER
+    }
+
+    fn parse_payload(payload: Option<&str>) -> Option<Self> {
+        Entity::try_from_bits(payload?.trim().parse().ok()?).ok()
+    }
+
+    fn write_payload(&self, f: &mut Formatter<'_>, name: &str) -> fmt::Result {
+        write!(f, "{}{}{}{}", name, PAYLOAD_OPEN, self.to_bits(), PAYLOAD_CLOSE)
+    }
+
+    fn merge(self, other: Self) -> Self {
+        other
+    }
+
+    fn encode_payload(&self, writer: &mut WireWriter) {
+        writer.write_varint(self.to_bits());
+    }
+
+    fn decode_payload(reader: &mut WireReader) -> Result<Self, WireError> {
+        let bits = reader.read_varint()?;
+        Entity::try_from_bits(bits).map_err(|_| WireError::InvalidEntity(bits))
+    }
+}
+
+pub trait CommandDispatch<T>
+where
+    T: Command,
+{
+    fn push_command(&mut self, command: T);
+}
+
+pub trait CommandHandler<T>
+where
+    T: Command,
+{
+    /// Handles the commands active this tick. Handlers are called every tick, so an
+    /// absent command is a released one.
+    fn handle_commands(&mut self, commands: &CommandSet<T>);
+}
+
+pub trait ExtCommandDispatch {
+    fn push_ext_command(&mut self, command: Box<dyn Command>);
+}
+
+pub trait ExtCommandHandler {
+    fn handle_ext_commands(&mut self, commands: &[&dyn Command]);
+}
+
+/// Collects the commands of type `T` out of a mixed list.
+pub fn downcast_commands<T>(commands: &[&dyn Command]) -> CommandSet<T>
+where
+    T: Command + Clone,
+{
+    commands
+        .iter()
+        .filter_map(|command| command.as_any().downcast_ref::<T>())
+        .cloned()
+        .collect()
+}
+
+/// Selects commands by name: `*` matches every command, `camera.*` every command of
+/// the `camera` namespace, and anything else a single command such as `camera.zoomIn`.
+#[derive(Clone, PartialEq)]
+pub enum CommandFilter {
+    All,
+    Namespace(String),
+    Exact(String),
+}
+
+impl CommandFilter {
+    /// Whether the command written `name`, payload excluded, passes the filter.
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            CommandFilter::All               => true,
+            CommandFilter::Namespace(prefix) => name
+                .strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.starts_with(NAMESPACE_SEPARATOR)),
+            CommandFilter::Exact(command)    => name == command,
+        }
+    }
+}
+
+impl fmt::Display for CommandFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandFilter::All               => write!(f, "{}", FILTER_WILDCARD),
+            CommandFilter::Namespace(prefix) => write!(f, "{}{}{}", prefix, NAMESPACE_SEPARATOR, FILTER_WILDCARD),
+            CommandFilter::Exact(command)    => write!(f, "{}", command),
+        }
+    }
+}
+
+impl FromString for CommandFilter {
+    fn from_string(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if s.is_empty() {
+            return None;
+        }
+        if s == FILTER_WILDCARD {
+            return Some(CommandFilter::All);
+        }
+        match s.strip_suffix(FILTER_WILDCARD).and_then(|s| s.strip_suffix(NAMESPACE_SEPARATOR)) {
+            Some(prefix) if !prefix.is_empty() => Some(CommandFilter::Namespace(prefix.to_string())),
+            Some(_) => None,
+            None => Some(CommandFilter::Exact(s.to_string())),
+        }
+    }
+}
+
+type RouteHandler = fn(&mut World, &[&dyn Command]);
+
+struct CommandRoute {
+    filter: CommandFilter,
+    handler: RouteHandler,
+}
+
+fn handle_resource_commands<H>(world: &mut World, commands: &[&dyn Command])
+where
+    H: Resource + ExtCommandHandler,
+{
+    if let Some(mut handler) = world.get_resource_mut::<H>() {
+        handler.handle_ext_commands(commands);
+    }
+}
+
+/// Routes the commands pushed during a frame to the handler resources registered
+/// for them.
+///
+/// Every frame, each route receives the commands matching its filter in the order
+/// they were pushed, routes being served in the order they were registered. Handlers
+/// are called even when no command matches, so they can tell a released binding from
+/// a held one. Commands no route matches are dropped.
+#[derive(Resource, Default)]
+pub struct CommandSystem {
+    ext_commands: Vec<Box<dyn Command>>,
+    routes: Vec<CommandRoute>,
+}
+
+impl CommandSystem {
+    /// Routes the commands matching `filter` to the `H` resource.
+    pub fn register<H>(&mut self, filter: CommandFilter)
+    where
+        H: Resource + ExtCommandHandler,
+    {
+        self.routes.push(CommandRoute { filter, handler: handle_resource_commands::<H> });
+    }
+
+    /// Commands pushed since the last dispatch.
+    pub fn pending(&self) -> impl Iterator<Item = &dyn Command> {
+        self.ext_commands.iter().map(|command| command.as_ref())
+    }
+
+    /// Takes the commands of type `T` pushed since the last dispatch out of the queue,
+    /// leaving the others in order. Input systems use it to complete commands with what
+    /// their bindings cannot know, such as the cursor position, before they are
+    /// recorded and dispatched.
+    pub fn take_commands<T>(&mut self) -> Vec<T>
+    where
+        T: Command + Clone,
+    {
+        let (taken, kept): (Vec<Box<dyn Command>>, _) =
+            std::mem::take(&mut self.ext_commands).into_iter().partition(|command| command.as_any().is::<T>());
+        self.ext_commands = kept;
+        taken.iter().filter_map(|command| command.as_any().downcast_ref::<T>()).cloned().collect()
+    }
+
+    /// Discards the commands pushed since the last dispatch.
+    pub fn clear(&mut self) {
+        self.ext_commands.clear();
+    }
+
+    /// Drains the queue, handing every route the commands matching its filter.
+    pub fn dispatch(&mut self, world: &mut World) {
+        let commands = std::mem::take(&mut self.ext_commands);
+        for route in &self.routes {
+            let routed: Vec<&dyn Command> = commands
+                .iter()
+                .map(|command| command.as_ref())
+                .filter(|command| route.filter.matches(command.name()))
+                .collect();
+            (route.handler)(world, &routed);
+        }
+    }
+}
+
+impl ExtCommandDispatch for CommandSystem {
+    fn push_ext_command(&mut self, command: Box<dyn Command>) {
+        self.ext_commands.push(command);
+    }
+}
+
+impl<T> CommandDispatch<T> for CommandSystem
+where
+    T: Command,
+{
+    fn push_command(&mut self, command: T) {
+        self.push_ext_command(Box::new(command));
+    }
+}
+
+/// Commands are dispatched to their handlers in this set, at the start of `Update`.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CommandDispatchSet;
+
+/// Adds the `CommandSystem`, `CommandRegistry` and `CommandRecorder`, and
+/// dispatches the `CommandSystem` every frame in `CommandDispatchSet`, right after
+/// recording or replacing its commands.
+pub struct CommandPlugin;
+
+impl Plugin for CommandPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CommandSystem>()
+            .init_resource::<CommandRegistry>()
+            .init_resource::<CommandRecorder>()
+            .add_systems(Update, (replay_commands, dispatch_commands).chain().in_set(CommandDispatchSet))
+            .add_systems(Last, save_recording_on_exit);
+    }
+}
+
+fn dispatch_commands(world: &mut World) {
+    world.resource_scope(|world, mut system: Mut<CommandSystem>| system.dispatch(world));
+}
+
+pub trait CommandAppExt {
+    /// Makes the `T` commands parsable through the `CommandRegistry`.
+    fn register_command<T>(&mut self) -> &mut Self
+    where
+        T: CommandType;
+
+    /// Routes the commands matching `filter`, such as `camera.*`, to the `H` resource.
+    fn add_command_handler<H>(&mut self, filter: &str) -> &mut Self
+    where
+        H: Resource + ExtCommandHandler;
+}
+
+impl CommandAppExt for App {
+    fn register_command<T>(&mut self) -> &mut Self
+    where
+        T: CommandType,
+    {
+        if !self.is_plugin_added::<CommandPlugin>() {
+            self.add_plugins(CommandPlugin);
+        }
+
+        self.world_mut().resource_mut::<CommandRegistry>().register::<T>();
+        self
+    }
+
+    fn add_command_handler<H>(&mut self, filter: &str) -> &mut Self
+    where
+        H: Resource + ExtCommandHandler,
+    {
+        if !self.is_plugin_added::<CommandPlugin>() {
+            self.add_plugins(CommandPlugin);
+        }
+
+        let filter = CommandFilter::from_string(filter).expect("the command filter is not empty");
+        self.world_mut().resource_mut::<CommandSystem>().register::<H>(filter);
+        self
+    }
+}
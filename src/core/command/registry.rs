@@ -0,0 +1,269 @@
+use crate::core::command::wire::{WireError, WireReader, WireWriter, WIRE_VERSION};
+use crate::core::command::{Command, NAMESPACE_SEPARATOR};
+use bevy::prelude::Resource;
+use std::any::TypeId;
+
+/// A command type whose variants are known to the `CommandRegistry`.
+pub trait CommandType: Command + Sized {
+    /// Namespace every variant name starts with, such as `camera` in `camera.zoomIn`.
+    const NAMESPACE: &'static str;
+
+    /// Identifies the command type on the wire; unique among registered types.
+    const ID: u16;
+
+    /// One value of every variant; variants carrying an axis use the default payload.
+    fn variants() -> Vec<Self>;
+}
+
+struct RegistryEntry {
+    type_id: TypeId,
+    namespace: &'static str,
+    id: u16,
+    names: Vec<&'static str>,
+    parse: fn(&str) -> Option<Box<dyn Command>>,
+    decode: fn(&mut WireReader) -> Result<Box<dyn Command>, WireError>,
+}
+
+fn parse_boxed<T>(s: &str) -> Option<Box<dyn Command>>
+where
+    T: CommandType,
+{
+    T::from_string(s).map(|command| Box::new(command) as Box<dyn Command>)
+}
+
+fn decode_boxed<T>(reader: &mut WireReader) -> Result<Box<dyn Command>, WireError>
+where
+    T: CommandType,
+{
+    T::decode(reader).map(|command| Box::new(command) as Box<dyn Command>)
+}
+
+/// Parses any registered command from its string form, and encodes commands of
+/// any registered type to and from the binary wire format.
+///
+/// On the wire, a packet is the `WIRE_VERSION` byte followed by the number of
+/// commands, then each command as its type id followed by its `Command::encode`
+/// bytes.
+#[derive(Resource, Default)]
+pub struct CommandRegistry {
+    entries: Vec<RegistryEntry>,
+}
+
+impl CommandRegistry {
+    /// Registers the `T` commands. Registering a type twice has no effect.
+    ///
+    /// Panics if another type already registered the same namespace or id, or if two
+    /// variants of `T` share an id.
+    pub fn register<T>(&mut self)
+    where
+        T: CommandType,
+    {
+        let type_id = TypeId::of::<T>();
+        if self.entries.iter().any(|entry| entry.type_id == type_id) {
+            return;
+        }
+        assert!(
+            self.namespace(T::NAMESPACE).is_none(),
+            "the `{}` command namespace is already registered",
+            T::NAMESPACE
+        );
+        assert!(
+            self.entries.iter().all(|entry| entry.id != T::ID),
+            "the command type id {} of `{}` is already registered",
+            T::ID,
+            T::NAMESPACE
+        );
+
+        let variants = T::variants();
+        let mut ids: Vec<u16> = variants.iter().map(Command::id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), variants.len(), "two `{}` commands share an id", T::NAMESPACE);
+
+        self.entries.push(RegistryEntry {
+            type_id,
+            namespace: T::NAMESPACE,
+            id: T::ID,
+            names: variants.iter().map(Command::name).collect(),
+            parse: parse_boxed::<T>,
+            decode: decode_boxed::<T>,
+        });
+    }
+
+    fn namespace(&self, namespace: &str) -> Option<&RegistryEntry> {
+        self.entries.iter().find(|entry| entry.namespace == namespace)
+    }
+
+    /// Parses `s`, such as `camera.zoomIn` or `camera.pan(0.5, 0)`, as the command
+    /// type registered for its namespace.
+    pub fn parse(&self, s: &str) -> Option<Box<dyn Command>> {
+        let (namespace, _) = s.trim().split_once(NAMESPACE_SEPARATOR)?;
+        self.namespace(namespace).and_then(|entry| (entry.parse)(s.trim()))
+    }
+
+    /// Writes `command` preceded by its type id.
+    pub fn encode_command(&self, command: &dyn Command, writer: &mut WireWriter) -> Result<(), WireError> {
+        let type_id = command.as_any().type_id();
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.type_id == type_id)
+            .ok_or_else(|| WireError::UnregisteredCommand(command.to_string()))?;
+
+        writer.write_varint(u64::from(entry.id));
+        command.encode(writer);
+        Ok(())
+    }
+
+    pub fn decode_command(&self, reader: &mut WireReader) -> Result<Box<dyn Command>, WireError> {
+        let id = reader.read_varint()?;
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| u64::from(entry.id) == id)
+            .ok_or(WireError::UnknownCommandType(id))?;
+        (entry.decode)(reader)
+    }
+
+    /// Encodes `commands` as a packet.
+    pub fn encode(&self, commands: &[&dyn Command]) -> Result<Vec<u8>, WireError> {
+        let mut writer = WireWriter::new();
+        writer.write_u8(WIRE_VERSION);
+        writer.write_varint(commands.len() as u64);
+        for command in commands {
+            self.encode_command(*command, &mut writer)?;
+        }
+        Ok(writer.into_bytes())
+    }
+
+    /// Decodes a packet written by `encode`.
+    pub fn decode(&self, bytes: &[u8]) -> Result<Vec<Box<dyn Command>>, WireError> {
+        let mut reader = WireReader::new(bytes);
+        let version = reader.read_u8()?;
+        if version != WIRE_VERSION {
+            return Err(WireError::UnsupportedVersion(version));
+        }
+
+        let count = reader.read_varint()?;
+        // Each command takes at least two bytes, which bounds the allocation
+        let mut commands = Vec::with_capacity((count as usize).min(reader.remaining() / 2));
+        for _ in 0..count {
+            commands.push(self.decode_command(&mut reader)?);
+        }
+
+        match reader.remaining() {
+            0 => Ok(commands),
+            count => Err(WireError::TrailingBytes(count)),
+        }
+    }
+
+    /// Names of every registered command, payload excluded, in registration order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().flat_map(|entry| entry.names.iter().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::camera::CameraCommand;
+    use crate::core::command::PAYLOAD_OPEN;
+    use crate::core::cursor::CursorCommand;
+    use crate::core::group::GroupCommand;
+    use crate::core::order::{QueueMode, Queued};
+    use crate::core::selection::SelectionCommand;
+    use crate::core::unit::{OrderCommand, OrderTarget, UnitCommand};
+    use bevy::math::{Vec2, Vec3};
+    use bevy::prelude::Entity;
+
+    fn registry() -> CommandRegistry {
+        let mut registry = CommandRegistry::default();
+        registry.register::<CameraCommand>();
+        registry.register::<CursorCommand>();
+        registry.register::<SelectionCommand>();
+        registry.register::<GroupCommand>();
+        registry.register::<UnitCommand>();
+        registry.register::<OrderCommand>();
+        registry
+    }
+
+    fn assert_round_trip<T>(command: T)
+    where
+        T: CommandType + PartialEq + std::fmt::Debug,
+    {
+        let s = command.to_string();
+        assert_eq!(T::from_string(&s).as_ref(), Some(&command), "`{}` does not round-trip", s);
+
+        let parsed = registry().parse(&s).unwrap_or_else(|| panic!("`{}` is not registered", s));
+        assert_eq!(parsed.as_any().downcast_ref::<T>(), Some(&command));
+        assert_eq!(parsed.to_string(), s);
+    }
+
+    #[test]
+    fn every_variant_round_trips() {
+        CameraCommand::variants().into_iter().for_each(assert_round_trip);
+        CursorCommand::variants().into_iter().for_each(assert_round_trip);
+        SelectionCommand::variants().into_iter().for_each(assert_round_trip);
+        GroupCommand::variants().into_iter().for_each(assert_round_trip);
+        UnitCommand::variants().into_iter().for_each(assert_round_trip);
+        OrderCommand::variants().into_iter().for_each(assert_round_trip);
+    }
+
+    #[test]
+    fn payloads_round_trip() {
+        assert_round_trip(CameraCommand::Rotate(-0.25));
+        assert_round_trip(CameraCommand::Zoom(3.5));
+        assert_round_trip(CameraCommand::Pan(Vec2::new(0.5, -2.0)));
+        assert_round_trip(CursorCommand::Move(Vec2::new(-1.0, 0.0)));
+        assert_round_trip(SelectionCommand::Box(Vec2::new(120.0, 48.5)));
+        assert_round_trip(SelectionCommand::Toggle(Vec2::new(-3.0, 0.25)));
+        assert_round_trip(GroupCommand::Assign(9));
+        assert_round_trip(GroupCommand::RecallAndCenter(0));
+        assert_round_trip(UnitCommand::Move { target: Vec3::new(4.0, 0.0, -2.5).into() });
+        assert_round_trip(UnitCommand::Attack { entity: Queued::new(Entity::from_raw(12), QueueMode::Append) });
+        assert_round_trip(UnitCommand::Stop { mode: QueueMode::Append });
+        assert_round_trip(UnitCommand::Patrol {
+            waypoints: Queued::new(vec![Vec3::new(1.0, 0.0, 2.0), Vec3::new(-3.0, 0.0, 0.5)], QueueMode::Append),
+        });
+        assert_round_trip(OrderCommand::Smart(OrderTarget::Entity(Entity::from_raw(3)).into()));
+        assert_round_trip(OrderCommand::AttackMove(Queued::new(OrderTarget::Ground(Vec3::new(0.5, 0.0, 8.0)), QueueMode::Append)));
+        assert_round_trip(OrderCommand::Patrol(Queued::new(OrderTarget::Cursor, QueueMode::Append)));
+    }
+
+    #[test]
+    fn names_cover_every_variant() {
+        let registry = registry();
+        let names: Vec<&str> = registry.names().collect();
+        assert!(names.contains(&"camera.moveForward"));
+        assert!(names.contains(&"camera.pan"));
+        assert!(names.contains(&"cursor.move"));
+        assert!(names.contains(&"order.attackMove"));
+
+        // The default payload of every variant is written after its name
+        let variants: Vec<String> = std::iter::empty()
+            .chain(CameraCommand::variants().iter().map(ToString::to_string))
+            .chain(CursorCommand::variants().iter().map(ToString::to_string))
+            .chain(SelectionCommand::variants().iter().map(ToString::to_string))
+            .chain(GroupCommand::variants().iter().map(ToString::to_string))
+            .chain(UnitCommand::variants().iter().map(ToString::to_string))
+            .chain(OrderCommand::variants().iter().map(ToString::to_string))
+            .collect();
+        assert_eq!(names.len(), variants.len());
+        for (name, variant) in names.iter().zip(&variants) {
+            assert_eq!(variant.split(PAYLOAD_OPEN).next(), Some(*name));
+            assert!(registry.parse(variant).is_some(), "`{}` is not registered", variant);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_commands() {
+        let registry = registry();
+        assert!(registry.parse("camera.fly").is_none());
+        assert!(registry.parse("units.stop").is_none());
+        assert!(registry.parse("camera.zoomIn(2)").is_none());
+        assert!(registry.parse("moveForward").is_none());
+        assert!(registry.parse("unit.move").is_none());
+        assert!(registry.parse("group.recall(256)").is_none());
+        assert!(registry.parse("order.smart(1, 2)").is_none());
+    }
+}
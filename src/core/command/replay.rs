@@ -0,0 +1,318 @@
+use crate::core::command::registry::CommandRegistry;
+use crate::core::command::wire::{WireError, WireReader, WireWriter};
+use crate::core::command::{Command, CommandSystem, ExtCommandDispatch};
+use bevy::log::warn;
+use bevy::prelude::{AppExit, EventReader, Mut, ResMut, Resource, Time, World};
+use bevy::time::TimeUpdateStrategy;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Bytes every recording file starts with.
+static RECORDING_MAGIC: &[u8; 4] = b"RTSR";
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(std::io::Error),
+    Wire(WireError),
+    NotARecording,
+}
+
+impl Display for RecordingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(error)     => write!(f, "{}", error),
+            RecordingError::Wire(error)   => write!(f, "{}", error),
+            RecordingError::NotARecording => write!(f, "not a command recording"),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+impl From<std::io::Error> for RecordingError {
+    fn from(error: std::io::Error) -> Self {
+        RecordingError::Io(error)
+    }
+}
+
+impl From<WireError> for RecordingError {
+    fn from(error: WireError) -> Self {
+        RecordingError::Wire(error)
+    }
+}
+
+/// The commands dispatched during one frame, as a `CommandRegistry` packet.
+pub struct RecordedFrame {
+    pub frame: u64,
+    /// Time elapsed since the previous frame.
+    pub delta: Duration,
+    pub packet: Vec<u8>,
+}
+
+/// Every frame of a recorded session, numbered from the first recorded frame.
+///
+/// On disk, a recording is `RTSR` followed by the frame count, then every frame as
+/// its number, its delta in nanoseconds, and the length and bytes of its packet.
+#[derive(Default)]
+pub struct Recording {
+    frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    /// Decodes the commands of every frame, checking the recording can be replayed.
+    pub fn commands(&self, registry: &CommandRegistry) -> Result<Vec<Vec<Box<dyn Command>>>, WireError> {
+        self.frames.iter().map(|frame| registry.decode(&frame.packet)).collect()
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = WireWriter::new();
+        RECORDING_MAGIC.iter().for_each(|&byte| writer.write_u8(byte));
+        writer.write_varint(self.frames.len() as u64);
+        for frame in &self.frames {
+            writer.write_varint(frame.frame);
+            writer.write_varint(frame.delta.as_nanos() as u64);
+            writer.write_varint(frame.packet.len() as u64);
+            frame.packet.iter().for_each(|&byte| writer.write_u8(byte));
+        }
+        writer.into_bytes()
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, RecordingError> {
+        let mut reader = WireReader::new(bytes);
+        for &byte in RECORDING_MAGIC {
+            if reader.read_u8().ok() != Some(byte) {
+                return Err(RecordingError::NotARecording);
+            }
+        }
+
+        let count = reader.read_varint()?;
+        let mut frames = Vec::with_capacity((count as usize).min(reader.remaining()));
+        for _ in 0..count {
+            let frame = reader.read_varint()?;
+            let delta = Duration::from_nanos(reader.read_varint()?);
+            let length = reader.read_varint()?;
+            let packet = (0..length).map(|_| reader.read_u8()).collect::<Result<Vec<u8>, WireError>>()?;
+            frames.push(RecordedFrame { frame, delta, packet });
+        }
+
+        match reader.remaining() {
+            0 => Ok(Self { frames }),
+            count => Err(WireError::TrailingBytes(count).into()),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        Self::decode(&std::fs::read(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        std::fs::write(path, self.encode())?;
+        Ok(())
+    }
+}
+
+enum ReplayMode {
+    Idle,
+    Recording { recording: Recording, path: Option<PathBuf> },
+    /// Waiting one frame so the first replayed frame gets its recorded delta.
+    Starting { recording: Recording },
+    Playing { recording: Recording, next: usize, strategy: Option<TimeUpdateStrategy> },
+}
+
+/// Records the commands dispatched through the `CommandSystem`, or replays a
+/// recording in place of the live ones.
+///
+/// While playing, the commands pushed from live input are discarded and every frame
+/// advances time by its recorded delta, so the session plays out as recorded. Playback
+/// starts on the frame after `play` and stops by itself after the last frame.
+#[derive(Resource)]
+pub struct CommandRecorder {
+    mode: ReplayMode,
+}
+
+impl Default for CommandRecorder {
+    fn default() -> Self {
+        Self { mode: ReplayMode::Idle }
+    }
+}
+
+impl CommandRecorder {
+    #[cfg(test)]
+    pub fn record(&mut self) {
+        self.mode = ReplayMode::Recording { recording: Recording::default(), path: None };
+    }
+
+    /// Records, saving the recording to `path` when it stops or the app exits.
+    pub fn record_to(&mut self, path: impl Into<PathBuf>) {
+        self.mode = ReplayMode::Recording { recording: Recording::default(), path: Some(path.into()) };
+    }
+
+    /// Stops recording, saving the recording if it was started with `record_to`.
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        match std::mem::replace(&mut self.mode, ReplayMode::Idle) {
+            ReplayMode::Recording { recording, path } => {
+                if let Some(path) = path
+                    && let Err(error) = recording.save(&path)
+                {
+                    warn!("could not save the recording to {}: {}", path.display(), error);
+                }
+                Some(recording)
+            }
+            mode => {
+                self.mode = mode;
+                None
+            }
+        }
+    }
+
+    pub fn play(&mut self, recording: Recording) {
+        self.mode = ReplayMode::Starting { recording };
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self.mode, ReplayMode::Starting { .. } | ReplayMode::Playing { .. })
+    }
+
+    /// Records or replays the commands of the current frame.
+    fn update(&mut self, world: &mut World) {
+        match &mut self.mode {
+            ReplayMode::Idle => {}
+            ReplayMode::Recording { recording, .. } => {
+                let registry = world.resource::<CommandRegistry>();
+                let commands: Vec<&dyn Command> = world.resource::<CommandSystem>().pending().collect();
+                let packet = registry.encode(&commands).unwrap_or_else(|error| {
+                    warn!("could not record frame {}: {}", recording.frames.len(), error);
+                    registry.encode(&[]).expect("empty packets always encode")
+                });
+                recording.frames.push(RecordedFrame {
+                    frame: recording.frames.len() as u64,
+                    delta: world.resource::<Time>().delta(),
+                    packet,
+                });
+            }
+            ReplayMode::Starting { .. } => {
+                let ReplayMode::Starting { recording } = std::mem::replace(&mut self.mode, ReplayMode::Idle) else {
+                    unreachable!();
+                };
+                let strategy = world.remove_resource::<TimeUpdateStrategy>();
+                world.resource_mut::<CommandSystem>().clear();
+                self.mode = ReplayMode::Playing { recording, next: 0, strategy };
+                self.schedule_next_delta(world);
+            }
+            ReplayMode::Playing { recording, next, .. } => {
+                let frame = &recording.frames[*next];
+                let commands = world.resource::<CommandRegistry>().decode(&frame.packet).unwrap_or_else(|error| {
+                    warn!("could not replay frame {}: {}", frame.frame, error);
+                    Vec::new()
+                });
+
+                let mut system = world.resource_mut::<CommandSystem>();
+                system.clear();
+                commands.into_iter().for_each(|command| system.push_ext_command(command));
+                *next += 1;
+                self.schedule_next_delta(world);
+            }
+        }
+    }
+
+    /// Makes the next frame last as long as the next recorded one, or restores the
+    /// time strategy once the recording is over.
+    fn schedule_next_delta(&mut self, world: &mut World) {
+        let ReplayMode::Playing { recording, next, strategy } = &mut self.mode else {
+            return;
+        };
+
+        match recording.frames.get(*next) {
+            Some(frame) => world.insert_resource(TimeUpdateStrategy::ManualDuration(frame.delta)),
+            None => {
+                match strategy.take() {
+                    Some(strategy) => world.insert_resource(strategy),
+                    None => world.insert_resource(TimeUpdateStrategy::default()),
+                }
+                self.mode = ReplayMode::Idle;
+            }
+        }
+    }
+}
+
+pub(super) fn replay_commands(world: &mut World) {
+    world.resource_scope(|world, mut recorder: Mut<CommandRecorder>| recorder.update(world));
+}
+
+pub(super) fn save_recording_on_exit(mut exits: EventReader<AppExit>, mut recorder: ResMut<CommandRecorder>) {
+    if exits.read().next().is_some() {
+        recorder.stop_recording();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::camera::{CameraCommand, CameraPlugin};
+    use crate::core::testing::TestApp;
+    use bevy::math::Vec3;
+    use bevy::prelude::{KeyCode, Transform};
+
+    #[test]
+    fn recordings_round_trip() {
+        let mut registry = CommandRegistry::default();
+        registry.register::<CameraCommand>();
+
+        let packets = [
+            registry.encode(&[]).unwrap(),
+            registry.encode(&[&CameraCommand::MoveLeft, &CameraCommand::Zoom(-0.5)]).unwrap(),
+        ];
+        let recording = Recording {
+            frames: packets
+                .into_iter()
+                .enumerate()
+                .map(|(frame, packet)| RecordedFrame { frame: frame as u64, delta: Duration::from_micros(16_667), packet })
+                .collect(),
+        };
+
+        let decoded = Recording::decode(&recording.encode()).unwrap();
+        let commands: Vec<Vec<String>> = decoded
+            .commands(&registry)
+            .unwrap()
+            .iter()
+            .map(|frame| frame.iter().map(|command| command.to_string()).collect())
+            .collect();
+
+        assert_eq!(commands, [vec![], vec!["camera.moveLeft".to_string(), "camera.zoom(-0.5)".to_string()]]);
+        assert_eq!(decoded.frames[1].delta, Duration::from_micros(16_667));
+        assert!(matches!(Recording::decode(b"RTS"), Err(RecordingError::NotARecording)));
+    }
+
+    #[test]
+    fn playback_reproduces_the_session() {
+        let start = Transform::from_xyz(0.0, 10.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y);
+        let mut app = TestApp::new(CameraPlugin);
+        let camera = app.spawn_camera(start.translation);
+
+        app.world_mut().resource_mut::<CommandRecorder>().record();
+        app.press(KeyCode::KeyW);
+        app.run(20);
+        app.press(KeyCode::KeyQ);
+        app.scroll(3.0);
+        app.run(15);
+        app.release(KeyCode::KeyW);
+        app.release(KeyCode::KeyQ);
+        app.run(5);
+        let recording = app.world_mut().resource_mut::<CommandRecorder>().stop_recording().unwrap();
+        let recorded = app.camera_transform();
+        assert_eq!(recording.frames.len(), 40);
+
+        *app.world_mut().get_mut::<Transform>(camera).unwrap() = start;
+        app.world_mut().resource_mut::<CommandRecorder>().play(recording);
+        // Live input is ignored during playback
+        app.press(KeyCode::KeyD);
+        while app.world().resource::<CommandRecorder>().is_playing() {
+            app.update();
+        }
+
+        // Same commands and same deltas, so the same floating point operations
+        assert_eq!(app.camera_transform(), recorded);
+    }
+}
@@ -0,0 +1,310 @@
+use crate::core::command::wire::{WireError, WireReader, WireWriter};
+use crate::core::command::Command;
+
+/// Number of variants a `CommandSet` can hold, one per bit of a `u16` command id.
+const CAPACITY: usize = u16::BITS as usize;
+
+/// The commands active during a tick, as a bitset over the single-bit ids of a
+/// `#[repr(u16)]` command enum.
+///
+/// A variant is either in the set or not; inserting a payload variant that is
+/// already present merges the payloads with `Command::merge`. Iteration follows the
+/// id bits in ascending order, so it is deterministic. The set never allocates.
+///
+/// Snapshots of a tick go to the wire with `encode` and `decode`. Hashing only covers
+/// the id bits, as payloads may hold floats: sets of the same variants share a hash
+/// whatever their payloads, which keeps it consistent with `PartialEq`.
+#[derive(Clone)]
+pub struct CommandSet<T>
+where
+    T: Command,
+{
+    bits: u16,
+    commands: [Option<T>; CAPACITY],
+}
+
+/// The slot of `id`, which must be a single bit.
+fn bit_index(id: u16) -> usize {
+    assert!(id.is_power_of_two(), "command ids in a `CommandSet` must be single bits, got {:#x}", id);
+    id.trailing_zeros() as usize
+}
+
+impl<T> CommandSet<T>
+where
+    T: Command,
+{
+    pub fn new() -> Self {
+        Self { bits: 0, commands: [const { None }; CAPACITY] }
+    }
+
+    /// The ids of the commands in the set, or-ed together.
+    pub fn bits(&self) -> u16 {
+        self.bits
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    pub fn insert(&mut self, command: T) {
+        let index = bit_index(command.id());
+        self.bits |= 1 << index;
+        self.commands[index] = Some(match self.commands[index].take() {
+            Some(present) => present.merge(command),
+            None => command,
+        });
+    }
+
+    pub fn remove(&mut self, id: u16) -> Option<T> {
+        let index = bit_index(id);
+        self.bits &= !(1 << index);
+        self.commands[index].take()
+    }
+
+    /// Whether the variant of `command` is in the set, whatever its payload.
+    pub fn contains(&self, command: &T) -> bool {
+        self.contains_id(command.id())
+    }
+
+    pub fn contains_id(&self, id: u16) -> bool {
+        self.bits & (1 << bit_index(id)) != 0
+    }
+
+    pub fn get(&self, id: u16) -> Option<&T> {
+        self.commands[bit_index(id)].as_ref()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.commands.iter().flatten()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Commands in either set; payload variants in both have their payloads merged.
+    pub fn union(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        let mut union = self.clone();
+        other.iter().cloned().for_each(|command| union.insert(command));
+        union
+    }
+
+    /// Commands of `self` whose variant is not in `other`.
+    pub fn difference(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        self.filter_bits(self.bits & !other.bits)
+    }
+
+    /// Commands active this tick that were not in `previous`.
+    pub fn newly_active(&self, previous: &Self) -> Self
+    where
+        T: Clone,
+    {
+        self.difference(previous)
+    }
+
+    /// Commands of `previous` that are no longer active this tick.
+    pub fn newly_released(&self, previous: &Self) -> Self
+    where
+        T: Clone,
+    {
+        previous.difference(self)
+    }
+
+    fn filter_bits(&self, bits: u16) -> Self
+    where
+        T: Clone,
+    {
+        let mut set = Self::new();
+        set.bits = bits;
+        for (index, command) in self.commands.iter().enumerate() {
+            if bits & (1 << index) != 0 {
+                set.commands[index] = command.clone();
+            }
+        }
+        set
+    }
+
+    /// Writes the id bits, then every command in iteration order.
+    pub fn encode(&self, writer: &mut WireWriter) {
+        writer.write_varint(u64::from(self.bits));
+        self.iter().for_each(|command| command.encode(writer));
+    }
+
+    pub fn decode(reader: &mut WireReader) -> Result<Self, WireError> {
+        let bits = reader.read_varint()?;
+        let mut set = Self::new();
+        for _ in 0..bits.count_ones() {
+            let command = T::decode(reader)?;
+            if !command.id().is_power_of_two() || set.contains(&command) {
+                return Err(WireError::InvalidCommandSet(bits));
+            }
+            set.insert(command);
+        }
+
+        if u64::from(set.bits) != bits {
+            return Err(WireError::InvalidCommandSet(bits));
+        }
+        Ok(set)
+    }
+}
+
+impl<T> Default for CommandSet<T>
+where
+    T: Command,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> PartialEq for CommandSet<T>
+where
+    T: Command + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits && self.commands == other.commands
+    }
+}
+
+impl<T> std::hash::Hash for CommandSet<T>
+where
+    T: Command,
+{
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.bits.hash(state);
+    }
+}
+
+impl<T> std::fmt::Debug for CommandSet<T>
+where
+    T: Command + std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T> FromIterator<T> for CommandSet<T>
+where
+    T: Command,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        iter.into_iter().for_each(|command| set.insert(command));
+        set
+    }
+}
+
+impl<T> Extend<T> for CommandSet<T>
+where
+    T: Command,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|command| self.insert(command));
+    }
+}
+
+impl<T> IntoIterator for CommandSet<T>
+where
+    T: Command,
+{
+    type Item = T;
+    type IntoIter = std::iter::Flatten<std::array::IntoIter<Option<T>, CAPACITY>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.commands.into_iter().flatten()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a CommandSet<T>
+where
+    T: Command,
+{
+    type Item = &'a T;
+    type IntoIter = std::iter::Flatten<std::slice::Iter<'a, Option<T>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.commands.iter().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::camera::CameraCommand;
+    use bevy::math::Vec2;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    #[test]
+    fn merges_payloads_of_the_same_variant() {
+        let set: CommandSet<CameraCommand> = [
+            CameraCommand::MoveForward,
+            CameraCommand::Zoom(0.5),
+            CameraCommand::MoveForward,
+            CameraCommand::Zoom(0.25),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.get(CameraCommand::Zoom(1.0).id()), Some(&CameraCommand::Zoom(0.75)));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [&CameraCommand::MoveForward, &CameraCommand::Zoom(0.75)]);
+    }
+
+    #[test]
+    fn diffs_frames() {
+        let previous: CommandSet<CameraCommand> = [CameraCommand::MoveForward, CameraCommand::RotateLeft].into_iter().collect();
+        let current: CommandSet<CameraCommand> = [CameraCommand::MoveForward, CameraCommand::ZoomIn].into_iter().collect();
+
+        assert_eq!(current.newly_active(&previous).iter().collect::<Vec<_>>(), [&CameraCommand::ZoomIn]);
+        assert_eq!(current.newly_released(&previous).iter().collect::<Vec<_>>(), [&CameraCommand::RotateLeft]);
+        assert_eq!(current.union(&previous).len(), 3);
+        assert!(current.contains(&CameraCommand::MoveForward));
+        assert!(!current.contains(&CameraCommand::RotateLeft));
+    }
+
+    #[test]
+    fn equal_sets_hash_alike() {
+        let hash = |set: &CommandSet<CameraCommand>| {
+            let mut hasher = DefaultHasher::new();
+            set.hash(&mut hasher);
+            hasher.finish()
+        };
+        let set: CommandSet<CameraCommand> = [CameraCommand::Zoom(0.5), CameraCommand::MoveUp].into_iter().collect();
+        let same: CommandSet<CameraCommand> = [CameraCommand::MoveUp, CameraCommand::Zoom(0.5)].into_iter().collect();
+        let other: CommandSet<CameraCommand> = [CameraCommand::MoveUp].into_iter().collect();
+
+        assert_eq!(set, same);
+        assert_eq!(hash(&set), hash(&same));
+        assert_ne!(hash(&set), hash(&other));
+    }
+
+    #[test]
+    #[should_panic(expected = "must be single bits")]
+    fn multi_bit_ids_are_rejected() {
+        CommandSet::<CameraCommand>::new().contains_id(0x3);
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        let set: CommandSet<CameraCommand> = [CameraCommand::MoveUp, CameraCommand::Pan(Vec2::new(0.5, -1.0))].into_iter().collect();
+        let mut writer = WireWriter::new();
+        set.encode(&mut writer);
+        let bytes = writer.into_bytes();
+
+        assert_eq!(CommandSet::decode(&mut WireReader::new(&bytes)), Ok(set));
+        let duplicated = [0x3, 0x1, 0x1];
+        assert_eq!(CommandSet::<CameraCommand>::decode(&mut WireReader::new(&duplicated)), Err(WireError::InvalidCommandSet(0x3)));
+    }
+}
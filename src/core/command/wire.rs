@@ -0,0 +1,287 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Version written at the start of every packet. Bump it whenever a command id,
+/// a command type id or a payload encoding changes.
+pub const WIRE_VERSION: u8 = 1;
+
+/// Longest LEB128 encoding of a `u64`.
+const MAX_VARINT_LENGTH: usize = 10;
+
+#[derive(Debug, PartialEq)]
+pub enum WireError {
+    UnexpectedEnd,
+    VarintOverflow,
+    UnsupportedVersion(u8),
+    UnknownCommandType(u64),
+    UnknownCommand { namespace: &'static str, id: u64 },
+    UnregisteredCommand(String),
+    InvalidCommandSet(u64),
+    InvalidEntity(u64),
+    InvalidTag(u8),
+    TrailingBytes(usize),
+}
+
+impl Display for WireError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WireError::UnexpectedEnd                    => write!(f, "unexpected end of data"),
+            WireError::VarintOverflow                   => write!(f, "varint longer than {} bytes", MAX_VARINT_LENGTH),
+            WireError::UnsupportedVersion(version)      => write!(f, "unsupported wire version {}", version),
+            WireError::UnknownCommandType(id)           => write!(f, "unknown command type {}", id),
+            WireError::UnknownCommand { namespace, id } => write!(f, "unknown `{}` command {}", namespace, id),
+            WireError::UnregisteredCommand(command)     => write!(f, "`{}` is not registered", command),
+            WireError::InvalidCommandSet(bits)          => write!(f, "command set {:#x} does not match its commands", bits),
+            WireError::InvalidEntity(bits)              => write!(f, "invalid entity {:#x}", bits),
+            WireError::InvalidTag(tag)                  => write!(f, "invalid payload tag {}", tag),
+            WireError::TrailingBytes(count)             => write!(f, "{} trailing bytes", count),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+/// Appends values to a byte buffer. Integers are written as LEB128 varints, signed
+/// ones zigzag-encoded first, and floats as their four little-endian bytes so they
+/// round-trip exactly.
+#[derive(Default)]
+pub struct WireWriter {
+    bytes: Vec<u8>,
+}
+
+impl WireWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+
+    pub fn write_signed_varint(&mut self, value: i64) {
+        self.write_varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// Reads back the values written by a `WireWriter`.
+pub struct WireReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> WireReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, WireError> {
+        let (&value, rest) = self.bytes.split_first().ok_or(WireError::UnexpectedEnd)?;
+        self.bytes = rest;
+        Ok(value)
+    }
+
+    pub fn read_varint(&mut self) -> Result<u64, WireError> {
+        let mut value: u64 = 0;
+        for index in 0..MAX_VARINT_LENGTH {
+            let byte = self.read_u8()?;
+            let bits = u64::from(byte & 0x7f);
+            // The tenth byte only has room for the top bit of a u64
+            if index == MAX_VARINT_LENGTH - 1 && bits > 1 {
+                return Err(WireError::VarintOverflow);
+            }
+            value |= bits << (7 * index);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(WireError::VarintOverflow)
+    }
+
+    pub fn read_signed_varint(&mut self) -> Result<i64, WireError> {
+        let value = self.read_varint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, WireError> {
+        if self.bytes.len() < 4 {
+            return Err(WireError::UnexpectedEnd);
+        }
+        let (value, rest) = self.bytes.split_at(4);
+        self.bytes = rest;
+        Ok(f32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::camera::CameraCommand;
+    use crate::core::command::registry::{CommandRegistry, CommandType};
+    use crate::core::command::Command;
+    use crate::core::cursor::CursorCommand;
+    use crate::core::testing::Rng;
+    use bevy::math::Vec2;
+
+    /// Mostly plain values, with special and arbitrary bit patterns mixed in.
+    fn random_f32(rng: &mut Rng) -> f32 {
+        const SPECIAL: [f32; 6] = [0.0, -0.0, 1.0, f32::MIN_POSITIVE, f32::MAX, f32::INFINITY];
+        match rng.below(4) {
+            0 => SPECIAL[rng.below(SPECIAL.len())],
+            1 => f32::from_bits(rng.next_u64() as u32),
+            _ => (rng.next_u64() % 20_000) as f32 / 100.0 - 100.0,
+        }
+    }
+
+    fn random_vec2(rng: &mut Rng) -> Vec2 {
+        Vec2::new(random_f32(rng), random_f32(rng))
+    }
+
+    fn registry() -> CommandRegistry {
+        let mut registry = CommandRegistry::default();
+        registry.register::<CameraCommand>();
+        registry.register::<CursorCommand>();
+        registry
+    }
+
+    fn random_command(rng: &mut Rng) -> Box<dyn Command> {
+        if rng.below(4) == 0 {
+            return Box::new(CursorCommand::Move(random_vec2(rng)));
+        }
+
+        let variants = CameraCommand::variants();
+        Box::new(match variants[rng.below(variants.len())] {
+            CameraCommand::Rotate(_) => CameraCommand::Rotate(random_f32(rng)),
+            CameraCommand::Pan(_)    => CameraCommand::Pan(random_vec2(rng)),
+            CameraCommand::Zoom(_)   => CameraCommand::Zoom(random_f32(rng)),
+            command => command,
+        })
+    }
+
+    /// Compares through the encoding itself, so NaN payloads compare by their bits.
+    fn assert_same(registry: &CommandRegistry, left: &dyn Command, right: &dyn Command) {
+        assert_eq!(left.to_string(), right.to_string());
+        assert_eq!(registry.encode(&[left]).unwrap(), registry.encode(&[right]).unwrap());
+    }
+
+    #[test]
+    fn varints_round_trip() {
+        let values = [0, 1, 127, 128, 300, 16_383, 16_384, u64::from(u32::MAX), u64::MAX - 1, u64::MAX];
+        let signed = [0, 1, -1, 63, -64, 64, i64::MAX, i64::MIN];
+
+        let mut writer = WireWriter::new();
+        values.iter().for_each(|&value| writer.write_varint(value));
+        signed.iter().for_each(|&value| writer.write_signed_varint(value));
+        let bytes = writer.into_bytes();
+
+        let mut reader = WireReader::new(&bytes);
+        for value in values {
+            assert_eq!(reader.read_varint(), Ok(value));
+        }
+        for value in signed {
+            assert_eq!(reader.read_signed_varint(), Ok(value));
+        }
+        assert_eq!(reader.remaining(), 0);
+    }
+
+    #[test]
+    fn small_varints_take_one_byte() {
+        let mut writer = WireWriter::new();
+        writer.write_varint(127);
+        writer.write_signed_varint(-64);
+        assert_eq!(writer.into_bytes().len(), 2);
+    }
+
+    #[test]
+    fn rejects_overlong_varints() {
+        let bytes = [0xff; 11];
+        assert_eq!(WireReader::new(&bytes).read_varint(), Err(WireError::VarintOverflow));
+
+        let mut bytes = [0xff; 10];
+        bytes[9] = 0x02;
+        assert_eq!(WireReader::new(&bytes).read_varint(), Err(WireError::VarintOverflow));
+    }
+
+    #[test]
+    fn random_packets_round_trip() {
+        let registry = registry();
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..1_000 {
+            let commands: Vec<Box<dyn Command>> = (0..rng.below(16)).map(|_| random_command(&mut rng)).collect();
+            let commands: Vec<&dyn Command> = commands.iter().map(|command| command.as_ref()).collect();
+
+            let bytes = registry.encode(&commands).unwrap();
+            let decoded = registry.decode(&bytes).unwrap();
+
+            assert_eq!(decoded.len(), commands.len());
+            for (command, decoded) in commands.iter().zip(&decoded) {
+                assert_same(&registry, *command, decoded.as_ref());
+            }
+        }
+    }
+
+    #[test]
+    fn digital_commands_are_compact() {
+        let registry = registry();
+        let commands: Vec<&dyn Command> = vec![&CameraCommand::MoveForward; 100];
+        // Version, count, then a type id and a command id per command
+        assert_eq!(registry.encode(&commands).unwrap().len(), 1 + 1 + 100 * 2);
+    }
+
+    #[test]
+    fn truncated_packets_fail() {
+        let registry = registry();
+        let pan = CameraCommand::Pan(Vec2::new(0.5, -1.0));
+        let zoom = CameraCommand::Zoom(2.0);
+        let bytes = registry.encode(&[&pan, &zoom]).unwrap();
+
+        for length in 0..bytes.len() {
+            assert!(registry.decode(&bytes[..length]).is_err(), "decoded a {} byte prefix", length);
+        }
+    }
+
+    #[test]
+    fn random_bytes_never_panic() {
+        let registry = registry();
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..10_000 {
+            let mut bytes: Vec<u8> = (0..rng.below(32)).map(|_| rng.next_u64() as u8).collect();
+            if let Some(version) = bytes.first_mut() {
+                *version = WIRE_VERSION;
+            }
+            let _ = registry.decode(&bytes);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_packets() {
+        let registry = registry();
+        assert_eq!(registry.decode(&[WIRE_VERSION + 1, 0]).err(), Some(WireError::UnsupportedVersion(WIRE_VERSION + 1)));
+        assert_eq!(registry.decode(&[WIRE_VERSION, 1, 99, 0]).err(), Some(WireError::UnknownCommandType(99)));
+        assert_eq!(
+            registry.decode(&[WIRE_VERSION, 1, 1, 3]).err(),
+            Some(WireError::UnknownCommand { namespace: CameraCommand::NAMESPACE, id: 3 })
+        );
+        assert_eq!(registry.decode(&[WIRE_VERSION, 0, 0]).err(), Some(WireError::TrailingBytes(1)));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::core::command::set::CommandSet;
+use crate::core::command::{downcast_commands, Command, CommandAppExt, CommandHandler, CommandDispatchSet, ExtCommandHandler};
+use crate::core::input::InputPlugin;
+use crate::core::SerializeEnum;
+use bevy::math::Vec2;
+use bevy::prelude::{App, IntoScheduleConfigs, Plugin, Query, ResMut, Resource, Update, Window, With};
+use bevy::window::PrimaryWindow;
+
+/// Moves the mouse cursor from devices without a pointer, such as gamepad sticks.
+/// `1.0` moves the cursor by the speed of the `CursorSystem`.
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, PartialEq, SerializeEnum, Command)]
+#[command(namespace = "cursor", id = 2)]
+pub enum CursorCommand {
+    Move(Vec2) = 0x1 << 0,
+}
+
+#[derive(Resource)]
+pub struct CursorSystem {
+    /// Pixels per second.
+    speed: f32,
+    move_delta: Vec2,
+}
+
+impl CursorSystem {
+    pub fn with_speed(speed: f32) -> Self {
+        Self { speed, move_delta: Vec2::ZERO }
+    }
+
+    /// Moves the cursor of `window`, starting from its centre if the cursor is outside.
+    pub fn update(&mut self, window: &mut Window) {
+        let delta = std::mem::take(&mut self.move_delta);
+        if delta == Vec2::ZERO {
+            return;
+        }
+
+        let size = window.size();
+        let position = window.cursor_position().unwrap_or(size / 2.0);
+        // Screen coordinates grow downwards
+        let position = position + Vec2::new(delta.x, -delta.y) * self.speed;
+        window.set_cursor_position(Some(position.clamp(Vec2::ZERO, size)));
+    }
+}
+
+impl CommandHandler<CursorCommand> for CursorSystem {
+    fn handle_commands(&mut self, commands: &CommandSet<CursorCommand>) {
+        for command in commands {
+            match command {
+                CursorCommand::Move(axis) => self.move_delta += *axis,
+            }
+        }
+    }
+}
+
+impl ExtCommandHandler for CursorSystem {
+    fn handle_ext_commands(&mut self, commands: &[&dyn Command]) {
+        self.handle_commands(&downcast_commands::<CursorCommand>(commands));
+    }
+}
+
+/// Drives the primary window's cursor from the `CursorCommand` bindings.
+pub struct CursorPlugin;
+
+impl Plugin for CursorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(InputPlugin::<CursorCommand>::default())
+            .register_command::<CursorCommand>()
+            .insert_resource(CursorSystem::with_speed(800.0))
+            .add_command_handler::<CursorSystem>("cursor.*")
+            .add_systems(Update, update_cursor.after(CommandDispatchSet));
+    }
+}
+
+fn update_cursor(
+    mut cursor: ResMut<CursorSystem>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if let Ok(mut window) = windows.single_mut() {
+        cursor.update(&mut window);
+    }
+}
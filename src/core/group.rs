@@ -0,0 +1,221 @@
+use crate::core::camera::CameraSystem;
+use crate::core::command::set::CommandSet;
+use crate::core::command::{downcast_commands, Command, CommandAppExt, CommandHandler, CommandDispatchSet, ExtCommandHandler};
+use crate::core::input::InputPlugin;
+use crate::core::selection::{SelectMode, Selectable, Selection};
+use crate::core::SerializeEnum;
+use bevy::math::Vec3;
+use bevy::prelude::{
+    App, Entity, GlobalTransform, IntoScheduleConfigs, Plugin, Query, RemovedComponents, ResMut, Resource, Update,
+};
+
+/// Number of control groups, one per digit key.
+pub const GROUP_COUNT: usize = 10;
+
+/// Control group commands, carrying the slot they act on. `Assign` replaces the
+/// group with the selection, `Add` adds the selection to it, `Recall` selects it and
+/// `RecallAndCenter` also centres the camera on it.
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, PartialEq, SerializeEnum, Command)]
+#[command(namespace = "group", id = 4)]
+pub enum GroupCommand {
+    Assign(u8)          = 0x1 << 0,
+    Add(u8)             = 0x1 << 1,
+    Recall(u8)          = 0x1 << 2,
+    RecallAndCenter(u8) = 0x1 << 3,
+}
+
+/// Ten slots of entities the player can select again in one key press.
+#[derive(Resource, Default)]
+pub struct ControlGroups {
+    groups: [Vec<Entity>; GROUP_COUNT],
+    pending: Vec<GroupCommand>,
+}
+
+impl ControlGroups {
+    /// The entities of group `slot`, empty for slots past `GROUP_COUNT`.
+    pub fn group(&self, slot: u8) -> &[Entity] {
+        self.groups.get(slot as usize).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn assign(&mut self, slot: u8, entities: &[Entity]) {
+        if let Some(group) = self.groups.get_mut(slot as usize) {
+            *group = entities.to_vec();
+        }
+    }
+
+    pub fn add(&mut self, slot: u8, entities: &[Entity]) {
+        if let Some(group) = self.groups.get_mut(slot as usize) {
+            for &entity in entities {
+                if !group.contains(&entity) {
+                    group.push(entity);
+                }
+            }
+        }
+    }
+
+    /// Removes `entity` from every group.
+    pub fn remove(&mut self, entity: Entity) {
+        self.groups.iter_mut().for_each(|group| group.retain(|&member| member != entity));
+    }
+
+    /// Applies the commands of this frame to the groups and the selection, returning
+    /// the point the camera should centre on, if any.
+    pub fn apply(&mut self, selection: &mut Selection, position: impl Fn(Entity) -> Option<Vec3>) -> Option<Vec3> {
+        let mut center = None;
+        for command in std::mem::take(&mut self.pending) {
+            match command {
+                GroupCommand::Assign(slot) => self.assign(slot, selection.entities()),
+                GroupCommand::Add(slot)    => self.add(slot, selection.entities()),
+                GroupCommand::Recall(slot) => selection.select(SelectMode::Replace, self.group(slot).iter().copied()),
+                GroupCommand::RecallAndCenter(slot) => {
+                    selection.select(SelectMode::Replace, self.group(slot).iter().copied());
+                    center = centroid(self.group(slot).iter().filter_map(|&entity| position(entity))).or(center);
+                }
+            }
+        }
+        center
+    }
+}
+
+fn centroid(points: impl Iterator<Item = Vec3>) -> Option<Vec3> {
+    let (sum, count) = points.fold((Vec3::ZERO, 0), |(sum, count), point| (sum + point, count + 1));
+    (count > 0).then(|| sum / count as f32)
+}
+
+impl CommandHandler<GroupCommand> for ControlGroups {
+    fn handle_commands(&mut self, commands: &CommandSet<GroupCommand>) {
+        self.pending.extend(commands.iter().copied());
+    }
+}
+
+impl ExtCommandHandler for ControlGroups {
+    fn handle_ext_commands(&mut self, commands: &[&dyn Command]) {
+        self.handle_commands(&downcast_commands::<GroupCommand>(commands));
+    }
+}
+
+/// Stores and recalls control groups of selected entities from the `GroupCommand`
+/// bindings. Needs the `SelectionPlugin` and the `CameraPlugin`.
+pub struct ControlGroupPlugin;
+
+impl Plugin for ControlGroupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(InputPlugin::<GroupCommand>::default())
+            .register_command::<GroupCommand>()
+            .init_resource::<ControlGroups>()
+            .add_command_handler::<ControlGroups>("group.*")
+            .add_systems(Update, (prune_groups, update_groups).chain().after(CommandDispatchSet));
+    }
+}
+
+fn update_groups(
+    mut groups: ResMut<ControlGroups>,
+    mut selection: ResMut<Selection>,
+    mut camera: ResMut<CameraSystem>,
+    transforms: Query<&GlobalTransform>,
+) {
+    let position = |entity| transforms.get(entity).ok().map(GlobalTransform::translation);
+    if let Some(center) = groups.apply(&mut selection, position) {
+        camera.center_on(center);
+    }
+}
+
+/// Forgets the entities that were despawned or stopped being selectable.
+fn prune_groups(mut removed: RemovedComponents<Selectable>, mut groups: ResMut<ControlGroups>) {
+    for entity in removed.read() {
+        groups.remove(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::camera::CameraPlugin;
+    use crate::core::command::{CommandDispatch, CommandSystem};
+    use crate::core::selection::SelectionPlugin;
+    use crate::core::testing::TestApp;
+    use crate::core::FromString;
+    use bevy::prelude::KeyCode;
+
+    fn app() -> (TestApp, [Entity; 3]) {
+        let mut app = TestApp::new((CameraPlugin, SelectionPlugin, ControlGroupPlugin));
+        app.spawn_camera(Vec3::new(0.0, 10.0, 10.0));
+        let units = [Vec3::new(2.0, 0.5, 0.0), Vec3::new(4.0, 0.5, 2.0), Vec3::new(-6.0, 0.5, -6.0)]
+            .map(|position| app.world_mut().spawn((Selectable::default(), GlobalTransform::from_translation(position))).id());
+        (app, units)
+    }
+
+    fn select(app: &mut TestApp, entities: &[Entity]) {
+        app.world_mut().resource_mut::<Selection>().select(SelectMode::Replace, entities.iter().copied());
+    }
+
+    fn tap(app: &mut TestApp, keys: &[KeyCode]) {
+        keys.iter().for_each(|&key| app.press(key));
+        app.update();
+        keys.iter().for_each(|&key| app.release(key));
+        app.update();
+    }
+
+    #[test]
+    fn digits_assign_add_and_recall() {
+        let (mut app, [a, b, c]) = app();
+        select(&mut app, &[a, b]);
+        tap(&mut app, &[KeyCode::ControlLeft, KeyCode::Digit1]);
+        assert_eq!(app.world().resource::<ControlGroups>().group(1), [a, b]);
+
+        select(&mut app, &[c]);
+        tap(&mut app, &[KeyCode::Digit1]);
+        assert_eq!(app.world().resource::<Selection>().entities(), [a, b]);
+
+        select(&mut app, &[c, a]);
+        tap(&mut app, &[KeyCode::ShiftRight, KeyCode::Digit1]);
+        assert_eq!(app.world().resource::<ControlGroups>().group(1), [a, b, c]);
+        assert!(app.world().resource::<ControlGroups>().group(2).is_empty());
+
+        app.world_mut().entity_mut(b).remove::<Selectable>();
+        app.update();
+        assert_eq!(app.world().resource::<ControlGroups>().group(1), [a, c]);
+    }
+
+    #[test]
+    fn double_tap_centers_the_camera() {
+        let (mut app, [a, b, _]) = app();
+        select(&mut app, &[a, b]);
+        tap(&mut app, &[KeyCode::ControlLeft, KeyCode::Digit2]);
+        select(&mut app, &[]);
+
+        tap(&mut app, &[KeyCode::Digit2]);
+        tap(&mut app, &[KeyCode::Digit2]);
+        assert_eq!(app.world().resource::<Selection>().entities(), [a, b]);
+
+        let camera = app.camera_transform();
+        let distance = -camera.translation.y / camera.forward().y;
+        let focus = camera.translation + camera.forward() * distance;
+        assert!(focus.distance(Vec3::new(3.0, 0.0, 1.0)) < 1e-4, "looking at {}", focus);
+        assert_eq!(camera.translation.y, 10.0);
+    }
+
+    #[test]
+    fn the_last_slot_of_a_frame_wins() {
+        let (mut app, [a, b, _]) = app();
+        select(&mut app, &[a]);
+        tap(&mut app, &[KeyCode::ControlLeft, KeyCode::Digit1]);
+        select(&mut app, &[b]);
+        tap(&mut app, &[KeyCode::ControlLeft, KeyCode::Digit2]);
+
+        let mut commands = app.world_mut().resource_mut::<CommandSystem>();
+        commands.push_command(GroupCommand::Recall(2));
+        commands.push_command(GroupCommand::Recall(1));
+        app.update();
+        assert_eq!(app.last_commands(), ["group.recall(2)", "group.recall(1)"]);
+        assert_eq!(app.world().resource::<Selection>().entities(), [a]);
+    }
+
+    #[test]
+    fn slots_are_always_written() {
+        assert_eq!(GroupCommand::Recall(0).to_string(), "group.recall(0)");
+        assert_eq!(GroupCommand::from_string("group.recallAndCenter(7)"), Some(GroupCommand::RecallAndCenter(7)));
+        assert_eq!(GroupCommand::from_string("group.recall"), None);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::core::input::keyboard::{Key, Modifier};
+use crate::core::input::profile::BindingProfile;
+use crate::core::input::{Binding, FromKey, FromKeys, InputSet, KeyBinding};
+use bevy::input::ButtonInput;
+use bevy::prelude::{App, Event, EventWriter, IntoScheduleConfigs, KeyCode, Plugin, PreUpdate, Res, ResMut, Resource};
+
+/// The key that aborts a capture instead of being recorded.
+const CANCEL_KEY: KeyCode = KeyCode::Escape;
+
+/// Records the next chord the player presses as a binding for a command.
+///
+/// While listening, and until the captured keys are released, command bindings are
+/// not dispatched so the captured keys do not also trigger gameplay.
+#[derive(Resource, Default)]
+pub struct BindingCapture {
+    command: Option<String>,
+    /// Keys of the last captured chord still held down.
+    held: Vec<KeyCode>,
+}
+
+impl BindingCapture {
+    /// Starts listening for a binding for `command`, replacing any pending capture.
+    pub fn listen(&mut self, command: impl ToString) {
+        self.command = Some(command.to_string());
+    }
+
+    pub fn cancel(&mut self) {
+        self.command = None;
+    }
+
+    pub fn is_listening(&self) -> bool {
+        self.command.is_some()
+    }
+}
+
+pub enum CaptureResult {
+    /// The chord was recorded. `conflicts` names the other commands of the active
+    /// profile already using it.
+    Captured { binding: KeyBinding, conflicts: Vec<String> },
+    Cancelled,
+}
+
+/// Sent when a capture started with `BindingCapture::listen` ends. The profile is
+/// left untouched; applying the binding is up to the receiver.
+#[derive(Event)]
+pub struct BindingCaptured {
+    pub command: String,
+    pub result: CaptureResult,
+}
+
+pub struct BindingCapturePlugin;
+
+impl Plugin for BindingCapturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BindingCapture>()
+            .add_event::<BindingCaptured>()
+            // After the bindings, so the key completing a capture does not also fire them
+            .add_systems(PreUpdate, capture_binding.after(InputSet));
+    }
+}
+
+/// Run condition for systems that must not see the keys of a chord being captured.
+pub fn not_capturing(capture: Res<BindingCapture>) -> bool {
+    !capture.is_listening() && capture.held.is_empty()
+}
+
+fn capture_binding(
+    mut capture: ResMut<BindingCapture>,
+    keys: Res<ButtonInput<KeyCode>>,
+    profile: Res<BindingProfile>,
+    mut events: EventWriter<BindingCaptured>,
+) {
+    if !capture.held.is_empty() {
+        capture.held.retain(|&key| keys.pressed(key));
+    }
+
+    let Some(command) = capture.command.clone() else {
+        return;
+    };
+
+    if keys.just_pressed(CANCEL_KEY) {
+        capture.cancel();
+        events.write(BindingCaptured { command, result: CaptureResult::Cancelled });
+        return;
+    }
+
+    // The chord ends with the first bindable key pressed on top of the held modifiers
+    let Some(&key) = keys
+        .get_just_pressed()
+        .find(|&&key| Key::from_keycode(key).is_some())
+    else {
+        return;
+    };
+
+    let mut chord: Vec<KeyCode> = keys
+        .get_pressed()
+        .filter(|&&key| Modifier::from_keycode(key).is_some())
+        .copied()
+        .collect();
+    chord.push(key);
+
+    if let Some(binding) = KeyBinding::from_inputs(chord.clone()) {
+        let context = profile.context(&command).unwrap_or_default();
+        let conflicts = profile
+            .commands_bound_to(&Binding::Chord(binding.clone()), context)
+            .into_iter()
+            .filter(|other| *other != command)
+            .collect();
+
+        capture.cancel();
+        capture.held = chord;
+        events.write(BindingCaptured { command, result: CaptureResult::Captured { binding, conflicts } });
+    }
+}
@@ -0,0 +1,206 @@
+use crate::core::input::profile::BindingProfile;
+use crate::core::input::{Input, InputSet};
+use crate::core::SerializeEnum;
+use bevy::prelude::{resource_changed, App, IntoScheduleConfigs, OnEnter, OnExit, Plugin, PreUpdate, Res, ResMut, Resource, States};
+use std::collections::{HashMap, HashSet};
+
+/// A layer of bindings. Profile lines belong to the context of the `[Context]`
+/// section they are in, `Gameplay` before any section.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, SerializeEnum)]
+pub enum InputContext {
+    #[default]
+    Gameplay,
+    BuildPlacement,
+    Menu,
+    Chat,
+}
+
+impl InputContext {
+    /// Whether the context hides `input` from the contexts below it, `bound` telling
+    /// if one of its own bindings uses `input`.
+    pub fn consumes(&self, input: Input, bound: bool) -> bool {
+        match self {
+            InputContext::Gameplay       => bound,
+            InputContext::BuildPlacement => bound,
+            InputContext::Menu           => true,
+            InputContext::Chat           => bound || matches!(input, Input::Key(_)),
+        }
+    }
+    /// Whether the context hides the pointer itself from the contexts below it, which
+    /// stops their pointer gestures such as edge scrolling.
+    pub fn takes_pointer(&self) -> bool {
+        match self {
+            InputContext::Gameplay       => false,
+            InputContext::BuildPlacement => false,
+            InputContext::Menu           => true,
+            InputContext::Chat           => false,
+        }
+    }
+}
+
+/// The stack of active input contexts, `Gameplay` at the bottom.
+///
+/// A binding only fires while its context is on the stack and its inputs are not
+/// consumed by a context above it, so the keys typed in `Chat` never reach the camera
+/// and placing a building can take over the mouse buttons of `Gameplay`.
+#[derive(Resource)]
+pub struct InputContexts {
+    stack: Vec<InputContext>,
+    /// Inputs used by the bindings of every context, across command types.
+    bound: HashMap<InputContext, HashSet<Input>>,
+}
+
+impl Default for InputContexts {
+    fn default() -> Self {
+        Self { stack: vec![InputContext::Gameplay], bound: HashMap::new() }
+    }
+}
+
+impl InputContexts {
+    /// Pushes `context` on top of the stack, moving it there if it is already active.
+    pub fn push(&mut self, context: InputContext) {
+        self.remove(context);
+        self.stack.push(context);
+    }
+
+    /// Removes `context` wherever it is in the stack, unless it is the bottom one.
+    pub fn remove(&mut self, context: InputContext) {
+        if let Some(index) = self.stack.iter().position(|&active| active == context)
+            && self.stack.len() > 1
+        {
+            self.stack.remove(index);
+        }
+    }
+
+    /// Whether bindings of `context` can see `input`.
+    pub fn reaches(&self, context: InputContext, input: Input) -> bool {
+        let Some(index) = self.stack.iter().position(|&active| active == context) else {
+            return false;
+        };
+
+        !self.stack[index + 1..].iter().any(|above| {
+            let bound = self.bound.get(above).is_some_and(|inputs| inputs.contains(&input));
+            above.consumes(input, bound)
+        })
+    }
+
+    /// Whether the pointer, as opposed to its buttons, reaches `context`.
+    pub fn pointer_reaches(&self, context: InputContext) -> bool {
+        let Some(index) = self.stack.iter().position(|&active| active == context) else {
+            return false;
+        };
+
+        !self.stack[index + 1..].iter().any(InputContext::takes_pointer)
+    }
+
+    pub fn set_bound_inputs(&mut self, context: InputContext, inputs: impl IntoIterator<Item = Input>) {
+        self.bound.insert(context, inputs.into_iter().collect());
+    }
+}
+
+/// Extension methods tying input contexts to game states.
+pub trait InputContextAppExt {
+    /// Pushes `context` when entering `state` and removes it when leaving.
+    fn add_state_input_context<S: States>(&mut self, state: S, context: InputContext) -> &mut Self;
+}
+
+impl InputContextAppExt for App {
+    fn add_state_input_context<S: States>(&mut self, state: S, context: InputContext) -> &mut Self {
+        if !self.is_plugin_added::<InputContextPlugin>() {
+            self.add_plugins(InputContextPlugin);
+        }
+
+        self.add_systems(OnEnter(state.clone()), move |mut contexts: ResMut<InputContexts>| contexts.push(context))
+            .add_systems(OnExit(state), move |mut contexts: ResMut<InputContexts>| contexts.remove(context))
+    }
+}
+
+/// Keeps track of the inputs bound in every context of the `BindingProfile`.
+pub struct InputContextPlugin;
+
+impl Plugin for InputContextPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputContexts>()
+            .add_systems(PreUpdate, load_bound_inputs.run_if(resource_changed::<BindingProfile>).before(InputSet));
+    }
+}
+
+fn load_bound_inputs(profile: Res<BindingProfile>, mut contexts: ResMut<InputContexts>) {
+    contexts.bound.clear();
+    for context in profile.contexts() {
+        contexts.set_bound_inputs(context, profile.bound_inputs(context));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::camera::CameraPlugin;
+    use crate::core::testing::TestApp;
+    use bevy::math::Vec2;
+    use bevy::prelude::{AppExtStates, KeyCode, MouseButton, NextState, StateTransition};
+    use bevy::state::app::StatesPlugin;
+
+    fn push(app: &mut TestApp, context: InputContext) {
+        app.world_mut().resource_mut::<InputContexts>().push(context);
+    }
+
+    #[test]
+    fn chat_consumes_the_keyboard() {
+        let mut app = TestApp::new(CameraPlugin);
+        app.press(KeyCode::KeyW);
+        app.update();
+        assert_eq!(app.last_commands(), ["camera.moveForward"]);
+
+        push(&mut app, InputContext::Chat);
+        app.press_mouse(MouseButton::Middle);
+        app.move_mouse(Vec2::new(100.0, 0.0));
+        app.update();
+        assert_eq!(app.last_commands(), ["camera.rotate(-0.5)"]);
+
+        app.world_mut().resource_mut::<InputContexts>().remove(InputContext::Chat);
+        app.update();
+        assert_eq!(app.last_commands(), ["camera.moveForward"]);
+    }
+
+    #[test]
+    fn higher_contexts_consume_their_bindings() {
+        let mut app = TestApp::new(CameraPlugin);
+        let profile = BindingProfile::parse(
+            "camera.moveForward = W\ncamera.moveLeft = A\n\n[BuildPlacement]\ncamera.zoomIn = W",
+        )
+        .unwrap();
+        assert_eq!(BindingProfile::parse(&profile.to_string()).unwrap().to_string(), profile.to_string());
+        app.world_mut().insert_resource(profile);
+
+        app.press(KeyCode::KeyW);
+        app.press(KeyCode::KeyA);
+        app.update();
+        assert_eq!(app.last_commands(), ["camera.moveForward", "camera.moveLeft"]);
+
+        push(&mut app, InputContext::BuildPlacement);
+        app.update();
+        assert_eq!(app.last_commands(), ["camera.moveLeft", "camera.zoomIn"]);
+    }
+
+    #[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    enum Screen {
+        #[default]
+        Playing,
+        Chatting,
+    }
+
+    #[test]
+    fn states_push_and_pop_contexts() {
+        let mut app = TestApp::new((CameraPlugin, StatesPlugin));
+        app.app().init_state::<Screen>().add_state_input_context(Screen::Chatting, InputContext::Chat);
+
+        app.world_mut().resource_mut::<NextState<Screen>>().set(Screen::Chatting);
+        app.world_mut().run_schedule(StateTransition);
+        assert_eq!(app.world().resource::<InputContexts>().stack, [InputContext::Gameplay, InputContext::Chat]);
+
+        app.world_mut().resource_mut::<NextState<Screen>>().set(Screen::Playing);
+        app.world_mut().run_schedule(StateTransition);
+        assert_eq!(app.world().resource::<InputContexts>().stack, [InputContext::Gameplay]);
+    }
+}
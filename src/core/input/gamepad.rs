@@ -0,0 +1,220 @@
+use crate::core::input::{InputState, KeyInput};
+use crate::core::SerializeEnum;
+use bevy::input::gamepad::{GamepadAxis, GamepadButton};
+use bevy::math::Vec2;
+use bevy::prelude::Resource;
+
+/// How a stick deflection past the dead zone maps to an axis value.
+#[derive(Clone, Copy, PartialEq, Eq, Default, SerializeEnum)]
+pub enum ResponseCurve {
+    #[default]
+    Linear,
+    Quadratic,
+    Cubic,
+}
+
+impl ResponseCurve {
+    fn apply(&self, value: f32) -> f32 {
+        match self {
+            ResponseCurve::Linear    => value,
+            ResponseCurve::Quadratic => value * value,
+            ResponseCurve::Cubic     => value * value * value,
+        }
+    }
+}
+
+/// Dead zone and response curve applied to both sticks.
+#[derive(Resource, Clone, Copy)]
+pub struct StickSettings {
+    /// Deflection below which a stick reads as centred, between 0 and 1.
+    pub dead_zone: f32,
+    pub curve: ResponseCurve,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        Self { dead_zone: 0.15, curve: ResponseCurve::Quadratic }
+    }
+}
+
+impl StickSettings {
+    /// Rescales `stick` so the dead zone edge reads 0 and full deflection reads 1,
+    /// then bends its length along the response curve.
+    pub fn apply(&self, stick: Vec2) -> Vec2 {
+        let length = stick.length();
+        if length <= self.dead_zone {
+            return Vec2::ZERO;
+        }
+
+        let scaled = ((length - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0);
+        stick / length * self.curve.apply(scaled)
+    }
+}
+
+/// Gamepad buttons and sticks usable in a `KeyBinding`.
+///
+/// Sticks are pressed while deflected past the dead zone. Their axis is a rate, so it
+/// is scaled by the frame time: full deflection for one second reads as `1.0`. The
+/// single-axis variants report their value on both axis components.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, SerializeEnum)]
+#[command(prefix = "Pad")]
+pub enum Pad {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    LeftThumb,
+    RightThumb,
+    Up,
+    Down,
+    Left,
+    Right,
+    #[command(name = "LeftStick")]
+    LeftStick,
+    #[command(name = "RightStick")]
+    RightStick,
+    #[command(name = "LeftStickX")]
+    LeftStickX,
+    #[command(name = "LeftStickY")]
+    LeftStickY,
+    #[command(name = "RightStickX")]
+    RightStickX,
+    #[command(name = "RightStickY")]
+    RightStickY,
+}
+
+impl Pad {
+    fn button(&self) -> Option<GamepadButton> {
+        match self {
+            Pad::South        => Some(GamepadButton::South),
+            Pad::East         => Some(GamepadButton::East),
+            Pad::North        => Some(GamepadButton::North),
+            Pad::West         => Some(GamepadButton::West),
+            Pad::LeftBumper   => Some(GamepadButton::LeftTrigger),
+            Pad::RightBumper  => Some(GamepadButton::RightTrigger),
+            Pad::LeftTrigger  => Some(GamepadButton::LeftTrigger2),
+            Pad::RightTrigger => Some(GamepadButton::RightTrigger2),
+            Pad::Select       => Some(GamepadButton::Select),
+            Pad::Start        => Some(GamepadButton::Start),
+            Pad::LeftThumb    => Some(GamepadButton::LeftThumb),
+            Pad::RightThumb   => Some(GamepadButton::RightThumb),
+            Pad::Up           => Some(GamepadButton::DPadUp),
+            Pad::Down         => Some(GamepadButton::DPadDown),
+            Pad::Left         => Some(GamepadButton::DPadLeft),
+            Pad::Right        => Some(GamepadButton::DPadRight),
+            _ => None,
+        }
+    }
+
+    /// The stick deflection after the dead zone and response curve, if this is a stick.
+    fn stick(&self, input: &InputState) -> Option<Vec2> {
+        let (x_axis, y_axis) = match self {
+            Pad::LeftStick | Pad::LeftStickX | Pad::LeftStickY    => (GamepadAxis::LeftStickX, GamepadAxis::LeftStickY),
+            Pad::RightStick | Pad::RightStickX | Pad::RightStickY => (GamepadAxis::RightStickX, GamepadAxis::RightStickY),
+            _ => return None,
+        };
+
+        let stick = input.gamepad.map_or(Vec2::ZERO, |gamepad| {
+            Vec2::new(gamepad.get(x_axis).unwrap_or(0.0), gamepad.get(y_axis).unwrap_or(0.0))
+        });
+        let stick = input.sticks.apply(stick);
+
+        Some(match self {
+            Pad::LeftStickX | Pad::RightStickX => Vec2::splat(stick.x),
+            Pad::LeftStickY | Pad::RightStickY => Vec2::splat(stick.y),
+            _ => stick,
+        })
+    }
+}
+
+impl KeyInput for Pad {
+    fn is_pressed(&self, input: &InputState) -> bool {
+        match (self.button(), self.stick(input)) {
+            (Some(button), _) => input.gamepad.is_some_and(|gamepad| gamepad.pressed(button)),
+            (None, Some(stick)) => stick != Vec2::ZERO,
+            (None, None) => false,
+        }
+    }
+
+    fn axis(&self, input: &InputState) -> Vec2 {
+        self.stick(input).map_or(Vec2::ONE, |stick| stick * input.dt)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::input::context::InputContexts;
+    use bevy::input::gamepad::Gamepad;
+    use bevy::input::ButtonInput;
+
+    fn assert_near(value: Vec2, expected: Vec2) {
+        assert!(value.distance(expected) < 1e-5, "{} is not {}", value, expected);
+    }
+
+    fn with_state<R>(gamepad: Option<&Gamepad>, dt: f32, f: impl FnOnce(&InputState) -> R) -> R {
+        let (keys, mouse_buttons) = (ButtonInput::default(), ButtonInput::default());
+        let (sticks, contexts) = (StickSettings::default(), InputContexts::default());
+        f(&InputState {
+            keys: &keys,
+            mouse_buttons: &mouse_buttons,
+            mouse_motion: Vec2::ZERO,
+            mouse_scroll: Vec2::ZERO,
+            gamepad,
+            sticks: &sticks,
+            dt,
+            contexts: &contexts,
+        })
+    }
+
+    #[test]
+    fn sticks_are_centred_within_the_dead_zone() {
+        let sticks = StickSettings::default();
+        assert_eq!(sticks.apply(Vec2::new(0.1, -0.05)), Vec2::ZERO);
+        assert_eq!(sticks.apply(Vec2::new(0.0, 0.15)), Vec2::ZERO);
+        assert_near(sticks.apply(Vec2::Y), Vec2::Y);
+
+        // Past full deflection, the direction is kept and the length capped
+        assert_near(sticks.apply(Vec2::new(1.0, 1.0)), Vec2::new(1.0, 1.0).normalize());
+    }
+
+    #[test]
+    fn curves_bend_the_deflection_past_the_dead_zone() {
+        // Halfway between the dead zone edge and full deflection
+        let half = Vec2::new(-0.575, 0.0);
+        let curve = |curve| StickSettings { dead_zone: 0.15, curve }.apply(half);
+        assert_near(curve(ResponseCurve::Linear), Vec2::new(-0.5, 0.0));
+        assert_near(curve(ResponseCurve::Quadratic), Vec2::new(-0.25, 0.0));
+        assert_near(curve(ResponseCurve::Cubic), Vec2::new(-0.125, 0.0));
+    }
+
+    #[test]
+    fn stick_axes_are_rates() {
+        let mut gamepad = Gamepad::default();
+        gamepad.analog_mut().set(GamepadAxis::LeftStickY, 1.0);
+        gamepad.analog_mut().set(GamepadAxis::RightStickX, 0.1);
+        gamepad.digital_mut().press(GamepadButton::South);
+
+        with_state(Some(&gamepad), 0.5, |input| {
+            assert!(Pad::LeftStick.is_pressed(input));
+            assert_near(Pad::LeftStick.axis(input), Vec2::new(0.0, 0.5));
+            assert_near(Pad::LeftStickY.axis(input), Vec2::splat(0.5));
+            assert!(!Pad::LeftStickX.is_pressed(input));
+            assert!(!Pad::RightStick.is_pressed(input));
+            assert!(Pad::South.is_pressed(input));
+            assert_eq!(Pad::South.axis(input), Vec2::ONE);
+        });
+
+        with_state(None, 0.5, |input| {
+            assert!(!Pad::LeftStick.is_pressed(input));
+            assert!(!Pad::South.is_pressed(input));
+        });
+    }
+}
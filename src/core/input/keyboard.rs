@@ -0,0 +1,426 @@
+use crate::core::input::{FromKey, InputState, KeyInput};
+use crate::core::SerializeEnum;
+use bevy::prelude::KeyCode;
+
+/// A key by its physical position, named after the key there on a US QWERTY
+/// keyboard.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, SerializeEnum)]
+pub enum Key {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    #[command(name = "0")]
+    Num0,
+    #[command(name = "1")]
+    Num1,
+    #[command(name = "2")]
+    Num2,
+    #[command(name = "3")]
+    Num3,
+    #[command(name = "4")]
+    Num4,
+    #[command(name = "5")]
+    Num5,
+    #[command(name = "6")]
+    Num6,
+    #[command(name = "7")]
+    Num7,
+    #[command(name = "8")]
+    Num8,
+    #[command(name = "9")]
+    Num9,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+    NumpadEnter,
+    Escape,
+    Enter,
+    Space,
+    Backspace,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    ArrowDown,
+    Tab,
+    Backquote,
+    Minus,
+    Equal,
+    BracketLeft,
+    BracketRight,
+    Backslash,
+    IntlBackslash,
+    Semicolon,
+    Quote,
+    Comma,
+    Period,
+    Slash,
+    Insert,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    CapsLock,
+    NumLock,
+    ScrollLock,
+    PrintScreen,
+    Pause,
+}
+
+impl Key {
+    pub fn keycode(&self) -> KeyCode {
+        match self {
+            Key::A              => KeyCode::KeyA,
+            Key::B              => KeyCode::KeyB,
+            Key::C              => KeyCode::KeyC,
+            Key::D              => KeyCode::KeyD,
+            Key::E              => KeyCode::KeyE,
+            Key::F              => KeyCode::KeyF,
+            Key::G              => KeyCode::KeyG,
+            Key::H              => KeyCode::KeyH,
+            Key::I              => KeyCode::KeyI,
+            Key::J              => KeyCode::KeyJ,
+            Key::K              => KeyCode::KeyK,
+            Key::L              => KeyCode::KeyL,
+            Key::M              => KeyCode::KeyM,
+            Key::N              => KeyCode::KeyN,
+            Key::O              => KeyCode::KeyO,
+            Key::P              => KeyCode::KeyP,
+            Key::Q              => KeyCode::KeyQ,
+            Key::R              => KeyCode::KeyR,
+            Key::S              => KeyCode::KeyS,
+            Key::T              => KeyCode::KeyT,
+            Key::U              => KeyCode::KeyU,
+            Key::V              => KeyCode::KeyV,
+            Key::W              => KeyCode::KeyW,
+            Key::X              => KeyCode::KeyX,
+            Key::Y              => KeyCode::KeyY,
+            Key::Z              => KeyCode::KeyZ,
+            Key::Num0           => KeyCode::Digit0,
+            Key::Num1           => KeyCode::Digit1,
+            Key::Num2           => KeyCode::Digit2,
+            Key::Num3           => KeyCode::Digit3,
+            Key::Num4           => KeyCode::Digit4,
+            Key::Num5           => KeyCode::Digit5,
+            Key::Num6           => KeyCode::Digit6,
+            Key::Num7           => KeyCode::Digit7,
+            Key::Num8           => KeyCode::Digit8,
+            Key::Num9           => KeyCode::Digit9,
+            Key::F1             => KeyCode::F1,
+            Key::F2             => KeyCode::F2,
+            Key::F3             => KeyCode::F3,
+            Key::F4             => KeyCode::F4,
+            Key::F5             => KeyCode::F5,
+            Key::F6             => KeyCode::F6,
+            Key::F7             => KeyCode::F7,
+            Key::F8             => KeyCode::F8,
+            Key::F9             => KeyCode::F9,
+            Key::F10            => KeyCode::F10,
+            Key::F11            => KeyCode::F11,
+            Key::F12            => KeyCode::F12,
+            Key::Numpad0        => KeyCode::Numpad0,
+            Key::Numpad1        => KeyCode::Numpad1,
+            Key::Numpad2        => KeyCode::Numpad2,
+            Key::Numpad3        => KeyCode::Numpad3,
+            Key::Numpad4        => KeyCode::Numpad4,
+            Key::Numpad5        => KeyCode::Numpad5,
+            Key::Numpad6        => KeyCode::Numpad6,
+            Key::Numpad7        => KeyCode::Numpad7,
+            Key::Numpad8        => KeyCode::Numpad8,
+            Key::Numpad9        => KeyCode::Numpad9,
+            Key::NumpadAdd      => KeyCode::NumpadAdd,
+            Key::NumpadSubtract => KeyCode::NumpadSubtract,
+            Key::NumpadMultiply => KeyCode::NumpadMultiply,
+            Key::NumpadDivide   => KeyCode::NumpadDivide,
+            Key::NumpadDecimal  => KeyCode::NumpadDecimal,
+            Key::NumpadEnter    => KeyCode::NumpadEnter,
+            Key::Escape         => KeyCode::Escape,
+            Key::Enter          => KeyCode::Enter,
+            Key::Space          => KeyCode::Space,
+            Key::Backspace      => KeyCode::Backspace,
+            Key::ArrowLeft      => KeyCode::ArrowLeft,
+            Key::ArrowRight     => KeyCode::ArrowRight,
+            Key::ArrowUp        => KeyCode::ArrowUp,
+            Key::ArrowDown      => KeyCode::ArrowDown,
+            Key::Tab            => KeyCode::Tab,
+            Key::Backquote      => KeyCode::Backquote,
+            Key::Minus          => KeyCode::Minus,
+            Key::Equal          => KeyCode::Equal,
+            Key::BracketLeft    => KeyCode::BracketLeft,
+            Key::BracketRight   => KeyCode::BracketRight,
+            Key::Backslash      => KeyCode::Backslash,
+            Key::IntlBackslash  => KeyCode::IntlBackslash,
+            Key::Semicolon      => KeyCode::Semicolon,
+            Key::Quote          => KeyCode::Quote,
+            Key::Comma          => KeyCode::Comma,
+            Key::Period         => KeyCode::Period,
+            Key::Slash          => KeyCode::Slash,
+            Key::Insert         => KeyCode::Insert,
+            Key::Delete         => KeyCode::Delete,
+            Key::Home           => KeyCode::Home,
+            Key::End            => KeyCode::End,
+            Key::PageUp         => KeyCode::PageUp,
+            Key::PageDown       => KeyCode::PageDown,
+            Key::CapsLock       => KeyCode::CapsLock,
+            Key::NumLock        => KeyCode::NumLock,
+            Key::ScrollLock     => KeyCode::ScrollLock,
+            Key::PrintScreen    => KeyCode::PrintScreen,
+            Key::Pause          => KeyCode::Pause,
+        }
+    }
+}
+
+impl KeyInput for Key {
+    fn is_pressed(&self, input: &InputState) -> bool {
+        input.keys.pressed(self.keycode())
+    }
+}
+
+impl FromKey for Key {
+    fn from_keycode(key: KeyCode) -> Option<Self>
+    where
+        Self: Sized
+    {
+        match key {
+            KeyCode::KeyA           =>  Some(Key::A),
+            KeyCode::KeyB           =>  Some(Key::B),
+            KeyCode::KeyC           =>  Some(Key::C),
+            KeyCode::KeyD           =>  Some(Key::D),
+            KeyCode::KeyE           =>  Some(Key::E),
+            KeyCode::KeyF           =>  Some(Key::F),
+            KeyCode::KeyG           =>  Some(Key::G),
+            KeyCode::KeyH           =>  Some(Key::H),
+            KeyCode::KeyI           =>  Some(Key::I),
+            KeyCode::KeyJ           =>  Some(Key::J),
+            KeyCode::KeyK           =>  Some(Key::K),
+            KeyCode::KeyL           =>  Some(Key::L),
+            KeyCode::KeyM           =>  Some(Key::M),
+            KeyCode::KeyN           =>  Some(Key::N),
+            KeyCode::KeyO           =>  Some(Key::O),
+            KeyCode::KeyP           =>  Some(Key::P),
+            KeyCode::KeyQ           =>  Some(Key::Q),
+            KeyCode::KeyR           =>  Some(Key::R),
+            KeyCode::KeyS           =>  Some(Key::S),
+            KeyCode::KeyT           =>  Some(Key::T),
+            KeyCode::KeyU           =>  Some(Key::U),
+            KeyCode::KeyV           =>  Some(Key::V),
+            KeyCode::KeyW           =>  Some(Key::W),
+            KeyCode::KeyX           =>  Some(Key::X),
+            KeyCode::KeyY           =>  Some(Key::Y),
+            KeyCode::KeyZ           =>  Some(Key::Z),
+            KeyCode::Digit0         =>  Some(Key::Num0),
+            KeyCode::Digit1         =>  Some(Key::Num1),
+            KeyCode::Digit2         =>  Some(Key::Num2),
+            KeyCode::Digit3         =>  Some(Key::Num3),
+            KeyCode::Digit4         =>  Some(Key::Num4),
+            KeyCode::Digit5         =>  Some(Key::Num5),
+            KeyCode::Digit6         =>  Some(Key::Num6),
+            KeyCode::Digit7         =>  Some(Key::Num7),
+            KeyCode::Digit8         =>  Some(Key::Num8),
+            KeyCode::Digit9         =>  Some(Key::Num9),
+            KeyCode::F1             =>  Some(Key::F1),
+            KeyCode::F2             =>  Some(Key::F2),
+            KeyCode::F3             =>  Some(Key::F3),
+            KeyCode::F4             =>  Some(Key::F4),
+            KeyCode::F5             =>  Some(Key::F5),
+            KeyCode::F6             =>  Some(Key::F6),
+            KeyCode::F7             =>  Some(Key::F7),
+            KeyCode::F8             =>  Some(Key::F8),
+            KeyCode::F9             =>  Some(Key::F9),
+            KeyCode::F10            =>  Some(Key::F10),
+            KeyCode::F11            =>  Some(Key::F11),
+            KeyCode::F12            =>  Some(Key::F12),
+            KeyCode::Numpad0        =>  Some(Key::Numpad0),
+            KeyCode::Numpad1        =>  Some(Key::Numpad1),
+            KeyCode::Numpad2        =>  Some(Key::Numpad2),
+            KeyCode::Numpad3        =>  Some(Key::Numpad3),
+            KeyCode::Numpad4        =>  Some(Key::Numpad4),
+            KeyCode::Numpad5        =>  Some(Key::Numpad5),
+            KeyCode::Numpad6        =>  Some(Key::Numpad6),
+            KeyCode::Numpad7        =>  Some(Key::Numpad7),
+            KeyCode::Numpad8        =>  Some(Key::Numpad8),
+            KeyCode::Numpad9        =>  Some(Key::Numpad9),
+            KeyCode::NumpadAdd      =>  Some(Key::NumpadAdd),
+            KeyCode::NumpadSubtract =>  Some(Key::NumpadSubtract),
+            KeyCode::NumpadMultiply =>  Some(Key::NumpadMultiply),
+            KeyCode::NumpadDivide   =>  Some(Key::NumpadDivide),
+            KeyCode::NumpadDecimal  =>  Some(Key::NumpadDecimal),
+            KeyCode::NumpadEnter    =>  Some(Key::NumpadEnter),
+            KeyCode::Escape         =>  Some(Key::Escape),
+            KeyCode::Enter          =>  Some(Key::Enter),
+            KeyCode::Space          =>  Some(Key::Space),
+            KeyCode::Backspace      =>  Some(Key::Backspace),
+            KeyCode::ArrowLeft      =>  Some(Key::ArrowLeft),
+            KeyCode::ArrowRight     =>  Some(Key::ArrowRight),
+            KeyCode::ArrowUp        =>  Some(Key::ArrowUp),
+            KeyCode::ArrowDown      =>  Some(Key::ArrowDown),
+            KeyCode::Tab            =>  Some(Key::Tab),
+            KeyCode::Backquote      =>  Some(Key::Backquote),
+            KeyCode::Minus          =>  Some(Key::Minus),
+            KeyCode::Equal          =>  Some(Key::Equal),
+            KeyCode::BracketLeft    =>  Some(Key::BracketLeft),
+            KeyCode::BracketRight   =>  Some(Key::BracketRight),
+            KeyCode::Backslash      =>  Some(Key::Backslash),
+            KeyCode::IntlBackslash  =>  Some(Key::IntlBackslash),
+            KeyCode::Semicolon      =>  Some(Key::Semicolon),
+            KeyCode::Quote          =>  Some(Key::Quote),
+            KeyCode::Comma          =>  Some(Key::Comma),
+            KeyCode::Period         =>  Some(Key::Period),
+            KeyCode::Slash          =>  Some(Key::Slash),
+            KeyCode::Insert         =>  Some(Key::Insert),
+            KeyCode::Delete         =>  Some(Key::Delete),
+            KeyCode::Home           =>  Some(Key::Home),
+            KeyCode::End            =>  Some(Key::End),
+            KeyCode::PageUp         =>  Some(Key::PageUp),
+            KeyCode::PageDown       =>  Some(Key::PageDown),
+            KeyCode::CapsLock       =>  Some(Key::CapsLock),
+            KeyCode::NumLock        =>  Some(Key::NumLock),
+            KeyCode::ScrollLock     =>  Some(Key::ScrollLock),
+            KeyCode::PrintScreen    =>  Some(Key::PrintScreen),
+            KeyCode::Pause          =>  Some(Key::Pause),
+            _ => None,
+        }
+    }
+}
+
+/// A keyboard layout the shipped binding profiles are written for. Profiles written
+/// for a layout name keys by their label on it, which `key` turns into the physical
+/// `Key` at the same place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, SerializeEnum)]
+pub enum KeyboardLayout {
+    #[default]
+    Qwerty,
+    Azerty,
+}
+
+impl KeyboardLayout {
+    /// The physical key labelled `label` on this layout.
+    pub fn key(&self, label: Key) -> Key {
+        match (self, label) {
+            (KeyboardLayout::Azerty, Key::A)         => Key::Q,
+            (KeyboardLayout::Azerty, Key::Q)         => Key::A,
+            (KeyboardLayout::Azerty, Key::Z)         => Key::W,
+            (KeyboardLayout::Azerty, Key::W)         => Key::Z,
+            (KeyboardLayout::Azerty, Key::M)         => Key::Semicolon,
+            (KeyboardLayout::Azerty, Key::Comma)     => Key::M,
+            (KeyboardLayout::Azerty, Key::Semicolon) => Key::Comma,
+            (_, label) => label,
+        }
+    }
+}
+
+/// Keys held together with the main input of a `KeyBinding`. The unsided variants
+/// accept either the left or the right key.
+///
+/// The declaration order is the canonical order modifiers are written in.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, SerializeEnum)]
+pub enum Modifier {
+    Ctrl,
+    LeftCtrl,
+    RightCtrl,
+    Shift,
+    LeftShift,
+    RightShift,
+    Alt,
+    LeftAlt,
+    RightAlt,
+    Super,
+    LeftSuper,
+    RightSuper,
+}
+
+impl Modifier {
+    pub const UNSIDED: [Modifier; 4] = [Modifier::Ctrl, Modifier::Shift, Modifier::Alt, Modifier::Super];
+
+    /// The modifier accepting either side of this one.
+    pub fn unsided(&self) -> Modifier {
+        match self {
+            Modifier::Ctrl  | Modifier::LeftCtrl  | Modifier::RightCtrl  => Modifier::Ctrl,
+            Modifier::Shift | Modifier::LeftShift | Modifier::RightShift => Modifier::Shift,
+            Modifier::Alt   | Modifier::LeftAlt   | Modifier::RightAlt   => Modifier::Alt,
+            Modifier::Super | Modifier::LeftSuper | Modifier::RightSuper => Modifier::Super,
+        }
+    }
+}
+
+impl KeyInput for Modifier {
+    fn is_pressed(&self, input: &InputState) -> bool {
+        match self {
+            Modifier::Shift      => input.keys.pressed(KeyCode::ShiftLeft) || input.keys.pressed(KeyCode::ShiftRight),
+            Modifier::Ctrl       => input.keys.pressed(KeyCode::ControlLeft) || input.keys.pressed(KeyCode::ControlRight),
+            Modifier::Alt        => input.keys.pressed(KeyCode::AltLeft) || input.keys.pressed(KeyCode::AltRight),
+            Modifier::Super      => input.keys.pressed(KeyCode::SuperLeft) || input.keys.pressed(KeyCode::SuperRight),
+            Modifier::LeftShift  => input.keys.pressed(KeyCode::ShiftLeft),
+            Modifier::RightShift => input.keys.pressed(KeyCode::ShiftRight),
+            Modifier::LeftCtrl   => input.keys.pressed(KeyCode::ControlLeft),
+            Modifier::RightCtrl  => input.keys.pressed(KeyCode::ControlRight),
+            Modifier::LeftAlt    => input.keys.pressed(KeyCode::AltLeft),
+            Modifier::RightAlt   => input.keys.pressed(KeyCode::AltRight),
+            Modifier::LeftSuper  => input.keys.pressed(KeyCode::SuperLeft),
+            Modifier::RightSuper => input.keys.pressed(KeyCode::SuperRight),
+        }
+    }
+}
+
+impl FromKey for Modifier {
+    fn from_keycode(key: KeyCode) -> Option<Self>
+    where
+        Self: Sized
+    {
+        match key {
+            KeyCode::ShiftLeft   | KeyCode::ShiftRight   => Some(Self::Shift),
+            KeyCode::ControlLeft | KeyCode::ControlRight => Some(Self::Ctrl),
+            KeyCode::AltLeft     | KeyCode::AltRight     => Some(Self::Alt),
+            KeyCode::SuperLeft   | KeyCode::SuperRight   => Some(Self::Super),
+            _ => None,
+        }
+    }
+}
+
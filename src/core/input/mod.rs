@@ -0,0 +1,699 @@
+use crate::core::command::set::CommandSet;
+use crate::core::command::{Command, CommandDispatch, CommandPlugin, CommandSystem};
+use crate::core::FromString;
+use bevy::ecs::system::SystemParam;
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit};
+use bevy::input::{ButtonInput, InputSystem};
+use bevy::math::Vec2;
+use bevy::prelude::{resource_changed, App, Gamepad, IntoScheduleConfigs, KeyCode, MouseButton, Plugin, PreUpdate, Query, Res, ResMut, Resource, SystemSet, Time};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+
+pub mod capture;
+pub mod context;
+pub mod gamepad;
+pub mod keyboard;
+pub mod mouse;
+pub mod pressed;
+pub mod profile;
+pub mod sequence;
+pub mod trigger;
+
+use capture::{not_capturing, BindingCapturePlugin};
+use context::{InputContext, InputContextPlugin, InputContexts};
+use gamepad::{Pad, StickSettings};
+use keyboard::{Key, KeyboardLayout, Modifier};
+use mouse::Mouse;
+use pressed::{resolve_pressed_bindings, PressedBindings, PressedBindingsPlugin};
+use profile::BindingProfile;
+use trigger::{Trigger, TriggerState};
+
+static KEY_BINDING_SEPARATOR: &str = " + ";
+static KEY_SEQUENCE_SEPARATOR: &str = " then ";
+
+/// Mouse wheel pixels counted as one scrolled line.
+const PIXELS_PER_LINE: f32 = 16.0;
+
+/// Snapshot of the device state bindings are evaluated against.
+pub struct InputState<'a> {
+    pub keys: &'a ButtonInput<KeyCode>,
+    pub mouse_buttons: &'a ButtonInput<MouseButton>,
+    /// Mouse motion this frame, in pixels.
+    pub mouse_motion: Vec2,
+    /// Mouse wheel scroll this frame, in lines.
+    pub mouse_scroll: Vec2,
+    /// The first connected gamepad, if any.
+    pub gamepad: Option<&'a Gamepad>,
+    pub sticks: &'a StickSettings,
+    /// Duration of this frame, in seconds.
+    pub dt: f32,
+    pub contexts: &'a InputContexts,
+}
+
+/// System parameter gathering the resources an `InputState` is built from.
+#[derive(SystemParam)]
+pub struct InputParams<'w, 's> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    mouse_buttons: Res<'w, ButtonInput<MouseButton>>,
+    mouse_motion: Res<'w, AccumulatedMouseMotion>,
+    mouse_scroll: Res<'w, AccumulatedMouseScroll>,
+    gamepads: Query<'w, 's, &'static Gamepad>,
+    sticks: Res<'w, StickSettings>,
+    time: Res<'w, Time>,
+    contexts: Res<'w, InputContexts>,
+}
+
+impl InputParams<'_, '_> {
+    pub fn state(&self) -> InputState<'_> {
+        let mouse_scroll = match self.mouse_scroll.unit {
+            MouseScrollUnit::Line => self.mouse_scroll.delta,
+            MouseScrollUnit::Pixel => self.mouse_scroll.delta / PIXELS_PER_LINE,
+        };
+
+        InputState {
+            keys: &self.keys,
+            mouse_buttons: &self.mouse_buttons,
+            mouse_motion: self.mouse_motion.delta,
+            mouse_scroll,
+            gamepad: self.gamepads.iter().next(),
+            sticks: &self.sticks,
+            dt: self.time.delta_secs(),
+            contexts: &self.contexts,
+        }
+    }
+}
+
+pub trait KeyInput {
+    fn is_pressed(&self, input: &InputState) -> bool;
+
+    /// The analog value of the input while pressed, handed to `Command::with_axis`.
+    fn axis(&self, _input: &InputState) -> Vec2 {
+        Vec2::ONE
+    }
+}
+
+pub trait FromKey {
+    fn from_keycode(key: KeyCode) -> Option<Self>
+    where
+        Self: Sized;
+}
+
+pub trait FromKeys {
+    fn from_inputs(keys: Vec<KeyCode>) -> Option<Self>
+    where
+        Self: Sized;
+}
+
+/// The main input of a `KeyBinding`, pressed together with its modifiers.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Input {
+    Key(Key),
+    Mouse(Mouse),
+    Pad(Pad),
+}
+
+impl KeyInput for Input {
+    fn is_pressed(&self, input: &InputState) -> bool {
+        match self {
+            Input::Key(key)     => key.is_pressed(input),
+            Input::Mouse(mouse) => mouse.is_pressed(input),
+            Input::Pad(pad)     => pad.is_pressed(input),
+        }
+    }
+
+    fn axis(&self, input: &InputState) -> Vec2 {
+        match self {
+            Input::Key(key)     => key.axis(input),
+            Input::Mouse(mouse) => mouse.axis(input),
+            Input::Pad(pad)     => pad.axis(input),
+        }
+    }
+}
+
+impl Display for Input {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Key(key)     => write!(f, "{}", key),
+            Input::Mouse(mouse) => write!(f, "{}", mouse),
+            Input::Pad(pad)     => write!(f, "{}", pad),
+        }
+    }
+}
+
+impl FromString for Input {
+    fn from_string(s: &str) -> Option<Self> {
+        Key::from_string(s)
+            .map(Input::Key)
+            .or_else(|| Mouse::from_string(s).map(Input::Mouse))
+            .or_else(|| Pad::from_string(s).map(Input::Pad))
+    }
+}
+
+/// How the modifiers held by the player are compared to those of a `KeyBinding`.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModifierMatch {
+    /// The binding's modifiers must be held, other modifiers may be held too.
+    #[default]
+    Subset,
+    /// The binding's modifiers must be held and no other modifier may be.
+    Exact,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    key: Input,
+    modifiers: Vec<Modifier>,
+}
+
+impl KeyBinding {
+    /// Builds a binding with its modifiers deduplicated and in canonical order, so
+    /// the same chord always compares and hashes equal.
+    pub fn new(key: Input, mut modifiers: Vec<Modifier>) -> Self {
+        modifiers.sort();
+        modifiers.dedup();
+        Self { key, modifiers }
+    }
+
+    pub fn key(&self) -> Input {
+        self.key
+    }
+
+    pub fn matches(&self, input: &InputState, modifier_match: ModifierMatch) -> bool {
+        if !self.is_pressed(input) {
+            return false;
+        }
+
+        match modifier_match {
+            ModifierMatch::Subset => true,
+            ModifierMatch::Exact => Modifier::UNSIDED.iter().all(|unsided| {
+                !unsided.is_pressed(input) || self.modifiers.iter().any(|modifier| modifier.unsided() == *unsided)
+            }),
+        }
+    }
+
+    /// Whether one press can fire both this binding and `other`: the same key with
+    /// the same modifiers, a side accepting either side of the same modifier.
+    pub fn overlaps(&self, other: &KeyBinding) -> bool {
+        self.key == other.key
+            && Modifier::UNSIDED.iter().all(|unsided| {
+                let sides = |chord: &KeyBinding| -> Vec<Modifier> {
+                    chord.modifiers.iter().copied().filter(|modifier| modifier.unsided() == *unsided).collect()
+                };
+                let (mine, theirs) = (sides(self), sides(other));
+                match (mine.is_empty(), theirs.is_empty()) {
+                    (true, true) => true,
+                    (false, false) => {
+                        mine.contains(unsided) || theirs.contains(unsided) || mine.iter().any(|side| theirs.contains(side))
+                    }
+                    _ => false,
+                }
+            })
+    }
+}
+
+impl KeyInput for KeyBinding {
+    fn is_pressed(&self, input: &InputState) -> bool {
+        self.modifiers.iter().all(|modifier| modifier.is_pressed(input)) && self.key.is_pressed(input)
+    }
+
+    fn axis(&self, input: &InputState) -> Vec2 {
+        self.key.axis(input)
+    }
+}
+
+impl FromKeys for KeyBinding {
+    fn from_inputs(keys: Vec<KeyCode>) -> Option<Self> {
+        let mut key = None;
+        let mut modifiers = Vec::new();
+
+        for k in keys {
+            if let Some(m) = Modifier::from_keycode(k) {
+                modifiers.push(m);
+            } else if let Some(k) = Key::from_keycode(k)
+                && key.is_none()
+            {
+                key = Some(k);
+            }
+        }
+
+        key.map(|key| KeyBinding::new(Input::Key(key), modifiers))
+    }
+}
+
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut s = String::new();
+        for modifier in &self.modifiers {
+            s.push_str(modifier.to_string().as_str());
+            s.push_str(KEY_BINDING_SEPARATOR);
+        }
+        s.push_str(self.key.to_string().as_str());
+        write!(f, "{}", s)
+    }
+}
+
+impl FromString for KeyBinding {
+    fn from_string(s: &str) -> Option<Self> {
+        let words: Vec<&str> = s.split(KEY_BINDING_SEPARATOR).collect();
+        let (last, rest) = words.split_last()?;
+        let key = Input::from_string(last)?;
+        let modifiers = rest
+            .iter()
+            .map(|&x| Modifier::from_string(x))
+            .collect::<Option<Vec<Modifier>>>()?;
+        Some(KeyBinding::new(key, modifiers))
+    }
+}
+
+/// A single chord, or chords pressed one after the other as in `G then 1`.
+///
+/// With `G then 1` bound, a `G` chord only fires once the sequence times out or is
+/// broken off by another key, as `PressedBindings` holds the first step back.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Chord(KeyBinding),
+    Sequence(Vec<KeyBinding>),
+}
+
+impl Binding {
+    /// Whether bindings of `context` see every input of this binding.
+    fn is_reachable(&self, input: &InputState, context: InputContext) -> bool {
+        match self {
+            Binding::Chord(chord)    => input.contexts.reaches(context, chord.key),
+            Binding::Sequence(steps) => steps.iter().all(|step| input.contexts.reaches(context, step.key)),
+        }
+    }
+
+    /// The binding written with the key labels of `layout`, as physical keys.
+    pub fn with_layout(&self, layout: KeyboardLayout) -> Binding {
+        let chord = |chord: &KeyBinding| match chord.key {
+            Input::Key(label) => KeyBinding::new(Input::Key(layout.key(label)), chord.modifiers.clone()),
+            _ => chord.clone(),
+        };
+        match self {
+            Binding::Chord(binding)  => Binding::Chord(chord(binding)),
+            Binding::Sequence(steps) => Binding::Sequence(steps.iter().map(chord).collect()),
+        }
+    }
+
+    /// Whether one press can fire both this binding and `other`, step by step for
+    /// sequences.
+    pub fn overlaps(&self, other: &Binding) -> bool {
+        match (self, other) {
+            (Binding::Chord(chord), Binding::Chord(other)) => chord.overlaps(other),
+            (Binding::Sequence(steps), Binding::Sequence(other)) => {
+                steps.len() == other.len() && steps.iter().zip(other).all(|(step, other)| step.overlaps(other))
+            }
+            _ => false,
+        }
+    }
+
+    fn axis(&self, input: &InputState) -> Vec2 {
+        match self {
+            Binding::Chord(chord) => chord.axis(input),
+            Binding::Sequence(_)  => Vec2::ONE,
+        }
+    }
+}
+
+impl From<KeyBinding> for Binding {
+    fn from(chord: KeyBinding) -> Self {
+        Binding::Chord(chord)
+    }
+}
+
+impl Display for Binding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Chord(chord) => write!(f, "{}", chord),
+            Binding::Sequence(steps) => {
+                let steps: Vec<String> = steps.iter().map(|step| step.to_string()).collect();
+                write!(f, "{}", steps.join(KEY_SEQUENCE_SEPARATOR))
+            }
+        }
+    }
+}
+
+impl FromString for Binding {
+    fn from_string(s: &str) -> Option<Self> {
+        let steps = s
+            .split(KEY_SEQUENCE_SEPARATOR)
+            .map(KeyBinding::from_string)
+            .collect::<Option<Vec<KeyBinding>>>()?;
+
+        match <[KeyBinding; 1]>::try_from(steps) {
+            Ok([chord]) => Some(Binding::Chord(chord)),
+            Err(steps) => Some(Binding::Sequence(steps)),
+        }
+    }
+}
+
+pub struct CommandBinding<T>
+where
+    T: Command,
+{
+    action: T,
+    bindings: Vec<Binding>,
+    trigger: Trigger,
+    context: InputContext,
+    modifier_match: ModifierMatch,
+    state: TriggerState,
+}
+
+impl<T> CommandBinding<T>
+where
+    T: Command,
+{
+    pub fn with_trigger(action: T, bindings: Vec<Binding>, trigger: Trigger) -> Self {
+        Self {
+            action,
+            bindings,
+            trigger,
+            context: InputContext::default(),
+            modifier_match: ModifierMatch::default(),
+            state: TriggerState::default(),
+        }
+    }
+
+    /// Moves the binding to `context`, `Gameplay` by default.
+    pub fn in_context(mut self, context: InputContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Sets how the modifiers of the chords are matched, `Subset` by default.
+    pub fn with_modifier_match(mut self, modifier_match: ModifierMatch) -> Self {
+        self.modifier_match = modifier_match;
+        self
+    }
+}
+
+impl<T> CommandBinding<T>
+where
+    T: Command + Clone,
+{
+    /// Advances the trigger state to `now` (in seconds) given the index of the
+    /// binding pressed this frame, and returns the action if the binding fires,
+    /// carrying the axis value of that binding.
+    fn update(&mut self, pressed: Option<usize>, input: &InputState, now: f32) -> Option<T> {
+        if !self.state.update(self.trigger, pressed.is_some(), now) {
+            return None;
+        }
+
+        let axis = pressed.map_or(Vec2::ONE, |index| self.bindings[index].axis(input));
+        Some(self.action.clone().with_axis(axis))
+    }
+}
+
+/// Every `CommandBinding` registered for commands of type `T`.
+#[derive(Resource)]
+pub struct CommandBindings<T>
+where
+    T: Command + Send + Sync + 'static,
+{
+    bindings: Vec<CommandBinding<T>>,
+}
+
+impl<T> Default for CommandBindings<T>
+where
+    T: Command + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl<T> CommandBindings<T>
+where
+    T: Command + Send + Sync + 'static,
+{
+    pub fn new(bindings: Vec<CommandBinding<T>>) -> Self {
+        Self { bindings }
+    }
+
+    pub fn set_bindings(&mut self, bindings: Vec<CommandBinding<T>>) {
+        self.bindings = bindings;
+    }
+}
+
+impl<T> CommandBindings<T>
+where
+    T: Command + Clone + Send + Sync + 'static,
+{
+    /// Returns the action of every binding firing at `now` (in seconds), given the
+    /// bindings `pressed` this frame. The payloads of actions fired by several
+    /// bindings add up.
+    pub fn active_commands(&mut self, pressed: &PressedBindings, input: &InputState, now: f32) -> CommandSet<T> {
+        self.bindings
+            .iter_mut()
+            .filter_map(|binding| {
+                let index = (0..binding.bindings.len()).find(|&index| {
+                    pressed.is_pressed(&binding.bindings[index], binding.context, binding.modifier_match)
+                });
+                binding.update(index, input, now)
+            })
+            .collect()
+    }
+}
+
+/// Systems that turn raw input into commands run in this set, before `Update`.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InputSet;
+
+/// Evaluates the `CommandBindings<T>` resource every frame and pushes the
+/// active commands into the `CommandSystem` resource.
+///
+/// The bindings are rebuilt from the `BindingProfile` resource whenever it changes.
+pub struct InputPlugin<T> {
+    _marker: PhantomData<T>,
+}
+
+impl<T> Default for InputPlugin<T> {
+    fn default() -> Self {
+        Self { _marker: PhantomData }
+    }
+}
+
+impl<T> Plugin for InputPlugin<T>
+where
+    T: Command + Clone + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<BindingCapturePlugin>() {
+            app.add_plugins(BindingCapturePlugin);
+        }
+        if !app.is_plugin_added::<CommandPlugin>() {
+            app.add_plugins(CommandPlugin);
+        }
+        if !app.is_plugin_added::<InputContextPlugin>() {
+            app.add_plugins(InputContextPlugin);
+        }
+        if !app.is_plugin_added::<PressedBindingsPlugin>() {
+            app.add_plugins(PressedBindingsPlugin);
+        }
+
+        app.init_resource::<BindingProfile>()
+            .init_resource::<StickSettings>()
+            .init_resource::<CommandBindings<T>>()
+            .configure_sets(PreUpdate, InputSet.after(InputSystem))
+            .add_systems(
+                PreUpdate,
+                (
+                    load_profile_bindings::<T>.run_if(resource_changed::<BindingProfile>),
+                    dispatch_bindings::<T>.run_if(not_capturing),
+                )
+                    .chain()
+                    .in_set(InputSet)
+                    .after(resolve_pressed_bindings),
+            );
+    }
+}
+
+fn load_profile_bindings<T>(profile: Res<BindingProfile>, mut bindings: ResMut<CommandBindings<T>>)
+where
+    T: Command + Send + Sync + 'static,
+{
+    bindings.set_bindings(profile.command_bindings());
+}
+
+fn dispatch_bindings<T>(
+    time: Res<Time>,
+    input: InputParams,
+    pressed: Res<PressedBindings>,
+    mut bindings: ResMut<CommandBindings<T>>,
+    mut queue: ResMut<CommandSystem>,
+) where
+    T: Command + Clone + Send + Sync + 'static,
+{
+    for command in bindings.active_commands(&pressed, &input.state(), time.elapsed_secs()) {
+        queue.push_command(command);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::camera::CameraPlugin;
+    use crate::core::group::ControlGroupPlugin;
+    use crate::core::selection::SelectionPlugin;
+    use crate::core::input::capture::{BindingCapture, BindingCaptured, CaptureResult};
+    use crate::core::testing::TestApp;
+    use bevy::prelude::Events;
+
+    fn chord(s: &str) -> KeyBinding {
+        KeyBinding::from_string(s).unwrap()
+    }
+
+    #[test]
+    fn chords_need_their_modifiers() {
+        let mut app = TestApp::new(CameraPlugin);
+        app.press(KeyCode::ControlLeft);
+        app.press(KeyCode::KeyA);
+        app.update();
+
+        app.with_input_state(|input| {
+            assert!(chord("Ctrl + A").is_pressed(input));
+            assert!(chord("LeftCtrl + A").is_pressed(input));
+            assert!(chord("A").is_pressed(input));
+            assert!(!chord("RightCtrl + A").is_pressed(input));
+            assert!(!chord("Shift + A").is_pressed(input));
+            assert!(!chord("A").matches(input, ModifierMatch::Exact));
+            assert!(chord("Ctrl + A").matches(input, ModifierMatch::Exact));
+        });
+    }
+
+    #[test]
+    fn more_specific_chords_win() {
+        let mut app = TestApp::new(CameraPlugin);
+        let profile = BindingProfile::parse("camera.moveForward = W\ncamera.zoomIn = Ctrl + W").unwrap();
+        app.world_mut().insert_resource(profile);
+
+        app.press(KeyCode::KeyW);
+        app.update();
+        assert_eq!(app.last_commands(), ["camera.moveForward"]);
+
+        app.press(KeyCode::ControlRight);
+        app.update();
+        assert_eq!(app.last_commands(), ["camera.zoomIn"]);
+    }
+
+    #[test]
+    fn specificity_spans_command_types() {
+        let mut app = TestApp::new((CameraPlugin, SelectionPlugin, ControlGroupPlugin));
+        let profile = BindingProfile::parse("camera.moveForward = W\ngroup.assign(1) = pressed: Ctrl + W").unwrap();
+        app.world_mut().insert_resource(profile);
+
+        app.press(KeyCode::ControlLeft);
+        app.press(KeyCode::KeyW);
+        app.update();
+        assert_eq!(app.last_commands(), ["group.assign(1)"]);
+
+        app.release(KeyCode::ControlLeft);
+        app.update();
+        assert_eq!(app.last_commands(), ["camera.moveForward"]);
+    }
+
+    #[test]
+    fn exact_chords_fire_without_other_modifiers() {
+        let mut app = TestApp::new(CameraPlugin);
+        let profile = BindingProfile::parse("camera.moveForward = exact: W\ncamera.zoomIn = exact: Ctrl + W").unwrap();
+        app.world_mut().insert_resource(profile);
+
+        app.press(KeyCode::KeyW);
+        app.update();
+        assert_eq!(app.last_commands(), ["camera.moveForward"]);
+
+        app.press(KeyCode::ShiftLeft);
+        app.update();
+        assert!(app.last_commands().is_empty());
+
+        app.press(KeyCode::ControlLeft);
+        app.update();
+        assert!(app.last_commands().is_empty());
+
+        app.release(KeyCode::ShiftLeft);
+        app.update();
+        assert_eq!(app.last_commands(), ["camera.zoomIn"]);
+    }
+
+    #[test]
+    fn bindings_are_ignored_while_capturing() {
+        let mut app = TestApp::new(CameraPlugin);
+        app.world_mut().resource_mut::<BindingCapture>().listen("camera.moveForward");
+        app.press(KeyCode::KeyW);
+        app.update();
+
+        assert!(app.last_commands().is_empty());
+        assert!(!app.world().resource::<BindingCapture>().is_listening());
+
+        // The captured key is swallowed until it is released
+        app.update();
+        assert!(app.last_commands().is_empty());
+        app.release(KeyCode::KeyW);
+        app.update();
+        app.press(KeyCode::KeyW);
+        app.update();
+        assert_eq!(app.last_commands(), ["camera.moveForward"]);
+    }
+
+    #[test]
+    fn captures_conflict_with_overlapping_chords_of_their_context() {
+        let mut app = TestApp::new(CameraPlugin);
+        let profile = BindingProfile::parse(
+            "camera.moveForward = LeftCtrl + 1\ncamera.moveBackward = Ctrl + Shift + 1\ncamera.zoomIn = RightCtrl + 1\n[Menu]\ncamera.zoomOut = Ctrl + 1",
+        )
+        .unwrap();
+        app.world_mut().insert_resource(profile);
+        app.world_mut().resource_mut::<BindingCapture>().listen("camera.moveLeft");
+        app.press(KeyCode::ControlRight);
+        app.press(KeyCode::Digit1);
+        app.update();
+
+        let mut events = app.world_mut().resource_mut::<Events<BindingCaptured>>();
+        let captured: Vec<BindingCaptured> = events.drain().collect();
+        let [BindingCaptured { result: CaptureResult::Captured { binding, conflicts }, .. }] = captured.as_slice() else {
+            panic!("one chord is captured");
+        };
+        assert_eq!(binding.to_string(), "Ctrl + 1");
+        assert_eq!(*conflicts, ["camera.moveForward", "camera.zoomIn"]);
+    }
+
+    #[test]
+    fn first_steps_fire_when_their_sequence_breaks_off() {
+        let mut app = TestApp::new((CameraPlugin, SelectionPlugin, ControlGroupPlugin));
+        let profile = BindingProfile::parse("group.recall(1) = pressed: B\ngroup.assign(1) = pressed: B then B").unwrap();
+        app.world_mut().insert_resource(profile);
+
+        // Held back until the sequence times out, then fired even though it was let go
+        app.press(KeyCode::KeyB);
+        app.update();
+        app.release(KeyCode::KeyB);
+        app.run(30);
+        assert!(app.all_commands().iter().all(|frame| frame.is_empty()));
+        app.run(30);
+        assert_eq!(app.all_commands().concat(), ["group.recall(1)"]);
+
+        // Fired by another key breaking the sequence off
+        app.press(KeyCode::KeyB);
+        app.update();
+        assert!(app.last_commands().is_empty());
+        app.press(KeyCode::KeyJ);
+        app.update();
+        assert_eq!(app.last_commands(), ["group.recall(1)"]);
+        app.release(KeyCode::KeyJ);
+        app.release(KeyCode::KeyB);
+        app.update();
+
+        // Never fired by a completed sequence
+        let fired = app.all_commands().len();
+        app.press(KeyCode::KeyB);
+        app.update();
+        app.release(KeyCode::KeyB);
+        app.update();
+        app.press(KeyCode::KeyB);
+        app.update();
+        app.run(60);
+        assert_eq!(app.all_commands()[fired..].concat(), ["group.assign(1)"]);
+    }
+}
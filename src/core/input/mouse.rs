@@ -0,0 +1,110 @@
+use crate::core::input::{InputState, KeyInput};
+use crate::core::SerializeEnum;
+use bevy::math::Vec2;
+use bevy::prelude::MouseButton;
+
+/// Axis units per pixel of mouse motion while dragging.
+const DRAG_SENSITIVITY: f32 = 0.005;
+/// Axis units per line of mouse wheel scroll.
+const WHEEL_SENSITIVITY: f32 = 0.1;
+
+/// Mouse buttons, wheel directions and drag gestures usable in a `KeyBinding`.
+///
+/// Wheel inputs are only active on the frames the wheel turns and drags only while
+/// the mouse moves with their button held; both report how far through `axis`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, SerializeEnum)]
+#[command(prefix = "Mouse")]
+pub enum Mouse {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+    #[command(name = "WheelUp")]
+    WheelUp,
+    #[command(name = "WheelDown")]
+    WheelDown,
+    #[command(name = "DragLeft")]
+    DragLeft,
+    #[command(name = "DragRight")]
+    DragRight,
+    #[command(name = "DragMiddle")]
+    DragMiddle,
+}
+
+impl KeyInput for Mouse {
+    fn is_pressed(&self, input: &InputState) -> bool {
+        match self {
+            Mouse::Left       => input.mouse_buttons.pressed(MouseButton::Left),
+            Mouse::Right      => input.mouse_buttons.pressed(MouseButton::Right),
+            Mouse::Middle     => input.mouse_buttons.pressed(MouseButton::Middle),
+            Mouse::Back       => input.mouse_buttons.pressed(MouseButton::Back),
+            Mouse::Forward    => input.mouse_buttons.pressed(MouseButton::Forward),
+            Mouse::WheelUp    => input.mouse_scroll.y > 0.0,
+            Mouse::WheelDown  => input.mouse_scroll.y < 0.0,
+            Mouse::DragLeft   => Mouse::Left.is_pressed(input) && input.mouse_motion != Vec2::ZERO,
+            Mouse::DragRight  => Mouse::Right.is_pressed(input) && input.mouse_motion != Vec2::ZERO,
+            Mouse::DragMiddle => Mouse::Middle.is_pressed(input) && input.mouse_motion != Vec2::ZERO,
+        }
+    }
+
+    fn axis(&self, input: &InputState) -> Vec2 {
+        match self {
+            Mouse::WheelUp | Mouse::WheelDown => Vec2::splat(input.mouse_scroll.y * WHEEL_SENSITIVITY),
+            Mouse::DragLeft | Mouse::DragRight | Mouse::DragMiddle => input.mouse_motion * DRAG_SENSITIVITY,
+            _ => Vec2::ONE,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::camera::{CameraCommand, CameraPlugin};
+    use crate::core::input::profile::BindingProfile;
+    use crate::core::testing::TestApp;
+
+    fn app() -> TestApp {
+        let mut app = TestApp::new(CameraPlugin);
+        let profile = BindingProfile::parse("camera.rotate = DragMiddle\ncamera.zoom(-1) = WheelDown").unwrap();
+        app.world_mut().insert_resource(profile);
+        app
+    }
+
+    #[test]
+    fn drags_need_their_button_and_motion() {
+        let mut app = app();
+        app.move_mouse(Vec2::new(20.0, -8.0));
+        app.update();
+        assert!(app.last_commands().is_empty());
+
+        app.press_mouse(MouseButton::Middle);
+        app.update();
+        assert!(app.last_commands().is_empty());
+
+        app.move_mouse(Vec2::new(20.0, -8.0));
+        app.update();
+        assert_eq!(app.last_commands(), [CameraCommand::Rotate(20.0 * DRAG_SENSITIVITY).to_string()]);
+        app.with_input_state(|input| {
+            assert_eq!(Mouse::DragMiddle.axis(input), Vec2::new(20.0, -8.0) * DRAG_SENSITIVITY);
+            assert!(!Mouse::DragLeft.is_pressed(input));
+        });
+    }
+
+    #[test]
+    fn wheel_bindings_fire_on_the_frames_it_turns() {
+        let mut app = app();
+        app.scroll(2.0);
+        app.update();
+        assert!(app.last_commands().is_empty());
+
+        app.scroll(-3.0);
+        app.update();
+        assert_eq!(app.last_commands(), [CameraCommand::Zoom(3.0 * WHEEL_SENSITIVITY).to_string()]);
+        app.with_input_state(|input| assert!(!Mouse::WheelUp.is_pressed(input)));
+
+        app.update();
+        assert!(app.last_commands().is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+use crate::core::input::capture::not_capturing;
+use crate::core::input::context::InputContext;
+use crate::core::input::profile::BindingProfile;
+use crate::core::input::sequence::SequenceTracker;
+use crate::core::input::{Binding, Input, InputParams, InputSet, InputState, KeyBinding, ModifierMatch};
+use bevy::prelude::{resource_changed, App, IntoScheduleConfigs, Plugin, PreUpdate, Res, ResMut, Resource, Time};
+use std::collections::HashMap;
+
+/// A binding of a profile line, with the context and modifier matching of that line.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BoundBinding {
+    pub binding: Binding,
+    pub context: InputContext,
+    pub modifier_match: ModifierMatch,
+}
+
+impl BoundBinding {
+    fn chord(&self) -> Option<&KeyBinding> {
+        match &self.binding {
+            Binding::Chord(chord) => Some(chord),
+            Binding::Sequence(_)  => None,
+        }
+    }
+}
+
+/// The bindings of the `BindingProfile` pressed this frame, resolved once for every
+/// command type before their `CommandBindings` are evaluated.
+///
+/// When chords sharing a main input are pressed together, only the ones with the most
+/// modifiers count as pressed, so `Ctrl + W` does not also trigger `W`, whichever
+/// command types they are bound to. Sequences count as pressed on the frame their last
+/// step is pressed. Bindings only see the inputs their `InputContext` is reached by.
+///
+/// The chords starting a sequence wait for it: with `G` and `G then H` bound, `G`
+/// fires once the sequence times out or another key breaks it off, for a single frame
+/// if it was let go meanwhile.
+#[derive(Resource, Default)]
+pub struct PressedBindings {
+    bound: Vec<BoundBinding>,
+    sequences: SequenceTracker,
+    pressed: Vec<BoundBinding>,
+    /// The chords pressed while the sequence holding back their key was in progress.
+    held_back: Vec<BoundBinding>,
+}
+
+impl PressedBindings {
+    pub fn is_pressed(&self, binding: &Binding, context: InputContext, modifier_match: ModifierMatch) -> bool {
+        self.pressed.iter().any(|pressed| {
+            pressed.binding == *binding && pressed.context == context && pressed.modifier_match == modifier_match
+        })
+    }
+
+    /// Resolves the bindings pressed at `now` (in seconds).
+    pub fn update(&mut self, input: &InputState, now: f32) {
+        let reachable: Vec<&BoundBinding> = self
+            .bound
+            .iter()
+            .filter(|bound| bound.binding.is_reachable(input, bound.context))
+            .collect();
+
+        let sequences: Vec<(&[KeyBinding], ModifierMatch)> = reachable
+            .iter()
+            .filter_map(|bound| match &bound.binding {
+                Binding::Sequence(steps) => Some((steps.as_slice(), bound.modifier_match)),
+                Binding::Chord(_) => None,
+            })
+            .collect();
+        let steps = self.sequences.update(&sequences, input, now);
+
+        let mut chords: Vec<&BoundBinding> = Vec::new();
+        for &bound in &reachable {
+            let Some(chord) = bound.chord().filter(|chord| chord.matches(input, bound.modifier_match)) else {
+                continue;
+            };
+            if self.sequences.is_held_back(chord.key) {
+                if !self.held_back.contains(bound) {
+                    self.held_back.push(bound.clone());
+                }
+            } else if !self.sequences.is_consumed(chord.key) {
+                chords.push(bound);
+            }
+        }
+
+        // Given back chords fire even if their key was let go while waiting
+        for bound in &self.held_back {
+            if bound.chord().is_some_and(|chord| steps.released.contains(&chord.key)) && !chords.contains(&bound) {
+                chords.push(bound);
+            }
+        }
+
+        let mut specificity: HashMap<Input, usize> = HashMap::new();
+        for chord in chords.iter().filter_map(|bound| bound.chord()) {
+            let most = specificity.entry(chord.key).or_default();
+            *most = (*most).max(chord.modifiers.len());
+        }
+
+        let most_specific = chords
+            .into_iter()
+            .filter(|bound| bound.chord().is_some_and(|chord| chord.modifiers.len() == specificity[&chord.key]));
+        let completed = reachable
+            .iter()
+            .copied()
+            .filter(|bound| matches!(&bound.binding, Binding::Sequence(sequence) if steps.completed.contains(sequence)));
+        self.pressed = most_specific.chain(completed).cloned().collect();
+
+        let sequences = &self.sequences;
+        self.held_back.retain(|bound| bound.chord().is_some_and(|chord| sequences.is_held_back(chord.key)));
+    }
+}
+
+/// Keeps the `PressedBindings` resource up to date, ahead of the `CommandBindings`
+/// systems of `InputSet`.
+pub struct PressedBindingsPlugin;
+
+impl Plugin for PressedBindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PressedBindings>().add_systems(
+            PreUpdate,
+            (
+                load_bound_bindings.run_if(resource_changed::<BindingProfile>),
+                resolve_pressed_bindings.run_if(not_capturing),
+            )
+                .chain()
+                .in_set(InputSet),
+        );
+    }
+}
+
+fn load_bound_bindings(profile: Res<BindingProfile>, mut pressed: ResMut<PressedBindings>) {
+    pressed.bound = profile.bound_bindings();
+}
+
+pub(super) fn resolve_pressed_bindings(time: Res<Time>, input: InputParams, mut pressed: ResMut<PressedBindings>) {
+    pressed.update(&input.state(), time.elapsed_secs());
+}
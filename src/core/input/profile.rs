@@ -0,0 +1,419 @@
+use crate::core::command::registry::CommandRegistry;
+use crate::core::command::Command;
+use crate::core::input::context::InputContext;
+use crate::core::input::keyboard::KeyboardLayout;
+use crate::core::input::trigger::Trigger;
+use crate::core::input::pressed::BoundBinding;
+use crate::core::input::{Binding, CommandBinding, Input, ModifierMatch};
+use crate::core::FromString;
+use bevy::prelude::Resource;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+static ASSIGNMENT_SEPARATOR: char = '=';
+static BINDING_LIST_SEPARATOR: char = ',';
+static TRIGGER_SEPARATOR: char = ':';
+static COMMENT_PREFIX: char = '#';
+static SECTION_PREFIX: char = '[';
+static SECTION_SUFFIX: char = ']';
+static EXACT_MODIFIERS: &str = "exact";
+
+pub static QWERTY_PROFILE: &str = include_str!("../../../assets/input/qwerty.bindings");
+pub static AZERTY_PROFILE: &str = include_str!("../../../assets/input/azerty.bindings");
+
+#[derive(Debug)]
+pub enum ProfileError {
+    Io(std::io::Error),
+    MissingAssignment { line: usize },
+    MissingCommand { line: usize },
+    UnknownCommand { line: usize, command: String },
+    InvalidBinding { line: usize, binding: String },
+    InvalidTrigger { line: usize, trigger: String },
+    InvalidContext { line: usize, context: String },
+}
+
+impl Display for ProfileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::Io(error) => write!(f, "{}", error),
+            ProfileError::MissingAssignment { line } => {
+                write!(f, "line {}: expected `command {} bindings`", line, ASSIGNMENT_SEPARATOR)
+            }
+            ProfileError::MissingCommand { line } => write!(f, "line {}: missing command name", line),
+            ProfileError::UnknownCommand { line, command } => {
+                write!(f, "line {}: unknown command `{}`", line, command)
+            }
+            ProfileError::InvalidBinding { line, binding } => {
+                write!(f, "line {}: invalid binding `{}`", line, binding)
+            }
+            ProfileError::InvalidTrigger { line, trigger } => {
+                write!(f, "line {}: invalid trigger `{}`", line, trigger)
+            }
+            ProfileError::InvalidContext { line, context } => {
+                write!(f, "line {}: invalid input context `{}`", line, context)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+impl From<std::io::Error> for ProfileError {
+    fn from(error: std::io::Error) -> Self {
+        ProfileError::Io(error)
+    }
+}
+
+struct ProfileEntry {
+    command: String,
+    bindings: Vec<Binding>,
+    trigger: Trigger,
+    modifier_match: ModifierMatch,
+    context: InputContext,
+}
+
+/// A set of `command = binding, binding` lines mapping command names to bindings,
+/// where each binding is a chord such as `Ctrl + Z` or a sequence such as `G then 1`.
+///
+/// The bindings may be prefixed with a trigger other than the default `held`, as in
+/// `group.recall = pressed: 1`, and with `exact` for their chords to only fire when
+/// no other modifier is held, as in `camera.zoomIn = exact: Ctrl + W`. A `[Context]`
+/// line puts the lines after it in that `InputContext`, the lines before any section
+/// being in `Gameplay`. Lines starting with `#` and blank lines are ignored.
+///
+/// Keys name physical positions, after the keys there on a US QWERTY keyboard, so a
+/// saved profile suits every layout. The shipped profiles are each written for a
+/// `KeyboardLayout` with its key labels, which `for_layout` turns into positions.
+///
+/// Commands of any type can share a profile; `command_bindings` only picks the lines
+/// whose command parses as `T`. `parse_checked` rejects the commands no registered
+/// type parses.
+#[derive(Resource)]
+pub struct BindingProfile {
+    entries: Vec<ProfileEntry>,
+}
+
+impl Default for BindingProfile {
+    fn default() -> Self {
+        Self::qwerty()
+    }
+}
+
+impl BindingProfile {
+    pub fn qwerty() -> Self {
+        Self::parse(QWERTY_PROFILE).expect("the QWERTY profile is valid")
+    }
+
+    pub fn azerty() -> Self {
+        Self::parse(AZERTY_PROFILE).expect("the AZERTY profile is valid").with_layout(KeyboardLayout::Azerty)
+    }
+
+    /// The shipped profile written for `layout`.
+    pub fn for_layout(layout: KeyboardLayout) -> Self {
+        match layout {
+            KeyboardLayout::Qwerty => Self::qwerty(),
+            KeyboardLayout::Azerty => Self::azerty(),
+        }
+    }
+
+    /// Parses a profile whatever its commands, so it may hold lines for command types
+    /// the app does not have.
+    pub fn parse(s: &str) -> Result<Self, ProfileError> {
+        Self::parse_commands(s, |_| true)
+    }
+
+    /// Parses a profile, failing on the first command `registry` cannot parse.
+    pub fn parse_checked(s: &str, registry: &CommandRegistry) -> Result<Self, ProfileError> {
+        Self::parse_commands(s, |command| registry.parse(command).is_some())
+    }
+
+    fn parse_commands(s: &str, is_known: impl Fn(&str) -> bool) -> Result<Self, ProfileError> {
+        let mut entries = Vec::new();
+        let mut context = InputContext::default();
+
+        for (index, line) in s.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(COMMENT_PREFIX) {
+                continue;
+            }
+
+            if let Some(section) = line.strip_prefix(SECTION_PREFIX).and_then(|line| line.strip_suffix(SECTION_SUFFIX)) {
+                let section = section.trim();
+                context = InputContext::from_string(section).ok_or_else(|| ProfileError::InvalidContext {
+                    line: line_number,
+                    context: section.to_string(),
+                })?;
+                continue;
+            }
+
+            let (command, bindings) = line
+                .split_once(ASSIGNMENT_SEPARATOR)
+                .ok_or(ProfileError::MissingAssignment { line: line_number })?;
+
+            let command = command.trim();
+            if command.is_empty() {
+                return Err(ProfileError::MissingCommand { line: line_number });
+            }
+            if !is_known(command) {
+                return Err(ProfileError::UnknownCommand { line: line_number, command: command.to_string() });
+            }
+
+            let mut bindings = bindings;
+            let mut trigger = Trigger::Held;
+            let mut modifier_match = ModifierMatch::Subset;
+            while let Some((prefix, rest)) = bindings.split_once(TRIGGER_SEPARATOR) {
+                let prefix = prefix.trim();
+                if prefix == EXACT_MODIFIERS {
+                    modifier_match = ModifierMatch::Exact;
+                } else {
+                    trigger = Trigger::from_string(prefix).ok_or_else(|| ProfileError::InvalidTrigger {
+                        line: line_number,
+                        trigger: prefix.to_string(),
+                    })?;
+                }
+                bindings = rest;
+            }
+
+            let bindings = bindings
+                .split(BINDING_LIST_SEPARATOR)
+                .map(str::trim)
+                .filter(|binding| !binding.is_empty())
+                .map(|binding| {
+                    Binding::from_string(binding).ok_or_else(|| ProfileError::InvalidBinding {
+                        line: line_number,
+                        binding: binding.to_string(),
+                    })
+                })
+                .collect::<Result<Vec<Binding>, ProfileError>>()?;
+
+            entries.push(ProfileEntry { command: command.to_string(), bindings, trigger, modifier_match, context });
+        }
+
+        Ok(Self { entries })
+    }
+
+    pub fn load(path: impl AsRef<Path>, registry: &CommandRegistry) -> Result<Self, ProfileError> {
+        Self::parse_checked(&std::fs::read_to_string(path)?, registry)
+    }
+
+    /// Turns the key labels of `layout` into the physical keys they are on.
+    fn with_layout(mut self, layout: KeyboardLayout) -> Self {
+        for entry in &mut self.entries {
+            entry.bindings = entry.bindings.iter().map(|binding| binding.with_layout(layout)).collect();
+        }
+        self
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ProfileError> {
+        std::fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// Builds a `CommandBinding` for every line whose command parses as `T`.
+    pub fn command_bindings<T>(&self) -> Vec<CommandBinding<T>>
+    where
+        T: Command,
+    {
+        self.entries
+            .iter()
+            .filter_map(|entry| {
+                T::from_string(&entry.command).map(|action| {
+                    CommandBinding::with_trigger(action, entry.bindings.clone(), entry.trigger)
+                        .in_context(entry.context)
+                        .with_modifier_match(entry.modifier_match)
+                })
+            })
+            .collect()
+    }
+
+    /// Replaces the bindings of `command`, appending a new `Gameplay` line if it has
+    /// none yet.
+    pub fn set_bindings(&mut self, command: &str, bindings: Vec<Binding>) {
+        match self.entries.iter_mut().find(|entry| entry.command == command) {
+            Some(entry) => entry.bindings = bindings,
+            None => self.entries.push(ProfileEntry {
+                command: command.to_string(),
+                bindings,
+                trigger: Trigger::Held,
+                modifier_match: ModifierMatch::Subset,
+                context: InputContext::default(),
+            }),
+        }
+    }
+
+    pub fn bindings(&self, command: &str) -> Option<&[Binding]> {
+        self.entries
+            .iter()
+            .find(|entry| entry.command == command)
+            .map(|entry| entry.bindings.as_slice())
+    }
+
+    /// Every context with at least one line.
+    pub fn contexts(&self) -> Vec<InputContext> {
+        let mut contexts: Vec<InputContext> = Vec::new();
+        for entry in &self.entries {
+            if !contexts.contains(&entry.context) {
+                contexts.push(entry.context);
+            }
+        }
+        contexts
+    }
+
+    /// Every binding of every line, whatever its command.
+    pub fn bound_bindings(&self) -> Vec<BoundBinding> {
+        self.entries
+            .iter()
+            .flat_map(|entry| {
+                entry.bindings.iter().map(|binding| BoundBinding {
+                    binding: binding.clone(),
+                    context: entry.context,
+                    modifier_match: entry.modifier_match,
+                })
+            })
+            .collect()
+    }
+
+    /// The main inputs of every binding of `context`, including sequence steps.
+    pub fn bound_inputs(&self, context: InputContext) -> Vec<Input> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.context == context)
+            .flat_map(|entry| entry.bindings.iter())
+            .flat_map(|binding| match binding {
+                Binding::Chord(chord)    => vec![chord.key()],
+                Binding::Sequence(steps) => steps.iter().map(|step| step.key()).collect(),
+            })
+            .collect()
+    }
+
+    /// The context of the line of `command`.
+    pub fn context(&self, command: &str) -> Option<InputContext> {
+        self.entries.iter().find(|entry| entry.command == command).map(|entry| entry.context)
+    }
+
+    /// Names of the commands of `context` a press of `binding` would also fire.
+    pub fn commands_bound_to(&self, binding: &Binding, context: InputContext) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.context == context)
+            .filter(|entry| entry.bindings.iter().any(|other| other.overlaps(binding)))
+            .map(|entry| entry.command.clone())
+            .collect()
+    }
+}
+
+impl Display for BindingProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut context = InputContext::default();
+        for entry in &self.entries {
+            if entry.context != context {
+                context = entry.context;
+                writeln!(f, "{}{}{}", SECTION_PREFIX, context, SECTION_SUFFIX)?;
+            }
+            let bindings: Vec<String> = entry.bindings.iter().map(|binding| binding.to_string()).collect();
+            let separator = format!("{} ", BINDING_LIST_SEPARATOR);
+            write!(f, "{} {} ", entry.command, ASSIGNMENT_SEPARATOR)?;
+            if entry.modifier_match == ModifierMatch::Exact {
+                write!(f, "{}{} ", EXACT_MODIFIERS, TRIGGER_SEPARATOR)?;
+            }
+            if entry.trigger != Trigger::Held {
+                write!(f, "{}{} ", entry.trigger, TRIGGER_SEPARATOR)?;
+            }
+            writeln!(f, "{}", bindings.join(&separator))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::camera::CameraCommand;
+    use crate::core::cursor::CursorCommand;
+    use crate::core::group::GroupCommand;
+    use crate::core::selection::SelectionCommand;
+    use crate::core::unit::{OrderCommand, UnitCommand};
+
+    fn registry() -> CommandRegistry {
+        let mut registry = CommandRegistry::default();
+        registry.register::<CameraCommand>();
+        registry.register::<CursorCommand>();
+        registry.register::<SelectionCommand>();
+        registry.register::<GroupCommand>();
+        registry.register::<UnitCommand>();
+        registry.register::<OrderCommand>();
+        registry
+    }
+
+    fn error_line(s: &str) -> usize {
+        match BindingProfile::parse_checked(s, &registry()) {
+            Err(ProfileError::MissingAssignment { line })
+            | Err(ProfileError::MissingCommand { line })
+            | Err(ProfileError::UnknownCommand { line, .. })
+            | Err(ProfileError::InvalidBinding { line, .. })
+            | Err(ProfileError::InvalidTrigger { line, .. })
+            | Err(ProfileError::InvalidContext { line, .. }) => line,
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("`{}` parses", s),
+        }
+    }
+
+    fn bindings(profile: &BindingProfile, command: &str) -> Vec<String> {
+        profile.bindings(command).unwrap_or_default().iter().map(Binding::to_string).collect()
+    }
+
+    #[test]
+    fn shipped_profiles_are_valid() {
+        let registry = registry();
+        BindingProfile::parse_checked(QWERTY_PROFILE, &registry).unwrap();
+        BindingProfile::parse_checked(AZERTY_PROFILE, &registry).unwrap();
+    }
+
+    #[test]
+    fn azerty_labels_load_as_physical_keys() {
+        let qwerty = BindingProfile::for_layout(KeyboardLayout::Qwerty);
+        let azerty = BindingProfile::for_layout(KeyboardLayout::Azerty);
+        assert_eq!(qwerty.to_string(), azerty.to_string());
+    }
+
+    #[test]
+    fn errors_report_their_line() {
+        let valid = "# comment\n\ncamera.moveBackward = S\n";
+        assert_eq!(error_line(&format!("{}camera.moveBackward S", valid)), 4);
+        assert_eq!(error_line(&format!("{} = S", valid)), 4);
+        assert_eq!(error_line(&format!("{}camera.fly = S", valid)), 4);
+        assert_eq!(error_line(&format!("{}camera.moveBackward = Ctrl +", valid)), 4);
+        assert_eq!(error_line(&format!("{}camera.moveBackward = often: S", valid)), 4);
+        assert_eq!(error_line(&format!("{}[Cockpit]", valid)), 4);
+    }
+
+    #[test]
+    fn unknown_commands_are_only_rejected_when_checked() {
+        assert!(BindingProfile::parse("camera.fly = S").is_ok());
+        assert!(matches!(
+            BindingProfile::parse_checked("camera.fly = S", &registry()),
+            Err(ProfileError::UnknownCommand { line: 1, command }) if command == "camera.fly"
+        ));
+    }
+
+    #[test]
+    fn saved_profiles_parse_back() {
+        let s = "camera.moveBackward = doubleTap: S, Ctrl + G then H\n\
+                 group.recall(1) = exact: pressed: 1\n\
+                 [Menu]\n\
+                 selection.click = pressed: MouseLeft\n";
+        let profile = BindingProfile::parse_checked(s, &registry()).unwrap();
+        let saved = profile.to_string();
+        let parsed = BindingProfile::parse_checked(&saved, &registry()).unwrap();
+
+        assert_eq!(parsed.to_string(), saved);
+        assert_eq!(bindings(&parsed, "camera.moveBackward"), ["S", "Ctrl + G then H"]);
+        assert_eq!(parsed.context("selection.click"), Some(InputContext::Menu));
+        assert_eq!(parsed.context("group.recall(1)"), Some(InputContext::Gameplay));
+        assert!(matches!(parsed.entries[0].trigger, Trigger::DoubleTap));
+        assert!(matches!(parsed.entries[1].trigger, Trigger::Pressed));
+        assert!(parsed.entries[1].modifier_match == ModifierMatch::Exact);
+    }
+}
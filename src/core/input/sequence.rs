@@ -0,0 +1,116 @@
+use crate::core::input::keyboard::Key;
+use crate::core::input::{FromKey, Input, InputState, KeyBinding, KeyInput, ModifierMatch};
+
+/// Longest delay between two steps of a sequence, in seconds.
+const SEQUENCE_TIMEOUT: f32 = 0.8;
+
+/// Follows the player's progress through sequence bindings such as `G then 1`.
+///
+/// A chord that continues a sequence is consumed: its main input does not count as
+/// pressed for chord bindings until it is released, so the steps do not also fire
+/// their standalone commands. The main input of the first step is held back instead,
+/// until the sequence goes on, which consumes it, or times out or is broken off by
+/// another key, which gives it back to the chord bindings.
+#[derive(Default)]
+pub struct SequenceTracker {
+    progress: Vec<KeyBinding>,
+    last_step_at: f32,
+    held: Vec<KeyBinding>,
+    consumed: Vec<Input>,
+    held_back: Option<Input>,
+}
+
+impl SequenceTracker {
+    /// Whether chord bindings must ignore `key`, consumed or held back by a sequence.
+    pub fn is_consumed(&self, key: Input) -> bool {
+        self.consumed.contains(&key) || self.held_back == Some(key)
+    }
+
+    pub fn is_held_back(&self, key: Input) -> bool {
+        self.held_back == Some(key)
+    }
+
+    /// Advances the tracker to `now` (in seconds).
+    pub fn update(
+        &mut self,
+        sequences: &[(&[KeyBinding], ModifierMatch)],
+        input: &InputState,
+        now: f32,
+    ) -> SequenceSteps {
+        let mut steps = SequenceSteps::default();
+        self.consumed.retain(|key| key.is_pressed(input));
+        if !self.progress.is_empty() && now - self.last_step_at > SEQUENCE_TIMEOUT {
+            self.break_off(&mut steps);
+        }
+
+        let mut pressed: Vec<KeyBinding> = Vec::new();
+        for (steps, modifier_match) in sequences {
+            for step in steps.iter() {
+                if !pressed.contains(step) && step.matches(input, *modifier_match) {
+                    pressed.push(step.clone());
+                }
+            }
+        }
+        let just_pressed: Vec<KeyBinding> = pressed
+            .iter()
+            .filter(|step| !self.held.contains(step))
+            .cloned()
+            .collect();
+        self.held = pressed;
+
+        // Any other key breaks the sequence in progress
+        let interrupted = input.keys.get_just_pressed().any(|&keycode| {
+            Key::from_keycode(keycode)
+                .is_some_and(|key| !just_pressed.iter().any(|step| step.key() == Input::Key(key)))
+        });
+        if interrupted {
+            self.break_off(&mut steps);
+        }
+
+        let is_prefix = |candidate: &[KeyBinding]| sequences.iter().any(|(steps, _)| steps.starts_with(candidate));
+
+        for step in just_pressed {
+            let mut candidate = self.progress.clone();
+            candidate.push(step.clone());
+            if !is_prefix(&candidate) {
+                self.break_off(&mut steps);
+                candidate = vec![step.clone()];
+                if !is_prefix(&candidate) {
+                    continue;
+                }
+            }
+
+            self.last_step_at = now;
+            if candidate.len() == 1 {
+                self.held_back = Some(step.key());
+            } else {
+                self.consumed.extend(self.held_back.take());
+                self.consumed.push(step.key());
+            }
+
+            if sequences.iter().any(|(steps, _)| *steps == candidate.as_slice()) {
+                self.progress.clear();
+                steps.completed.push(candidate);
+            } else {
+                self.progress = candidate;
+            }
+        }
+
+        steps
+    }
+
+    /// Drops the sequence in progress, giving its first key back to chord bindings.
+    fn break_off(&mut self, steps: &mut SequenceSteps) {
+        self.progress.clear();
+        steps.released.extend(self.held_back.take());
+    }
+}
+
+/// What a frame of `SequenceTracker::update` did to the sequences.
+#[derive(Default)]
+pub struct SequenceSteps {
+    /// The sequences whose last step was pressed this frame.
+    pub completed: Vec<Vec<KeyBinding>>,
+    /// The keys held back by a sequence that timed out or was broken off this frame.
+    pub released: Vec<Input>,
+}
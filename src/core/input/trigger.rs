@@ -0,0 +1,98 @@
+use crate::core::command::split_payload;
+use crate::core::FromString;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Longest delay between the two presses of a double tap, in seconds.
+const DOUBLE_TAP_INTERVAL: f32 = 0.3;
+
+/// When a `CommandBinding` fires relative to its key bindings being pressed.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Trigger {
+    /// Every frame while pressed.
+    #[default]
+    Held,
+    /// Once, on the frame the binding is pressed.
+    Pressed,
+    /// Once, on the frame the binding is released.
+    Released,
+    /// Once, after the binding has been held for the given number of seconds.
+    Hold(f32),
+    /// Once, when the binding is pressed twice within `DOUBLE_TAP_INTERVAL`.
+    DoubleTap,
+}
+
+impl Display for Trigger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Trigger::Held           => write!(f, "held"),
+            Trigger::Pressed        => write!(f, "pressed"),
+            Trigger::Released       => write!(f, "released"),
+            Trigger::Hold(duration) => write!(f, "hold({})", duration),
+            Trigger::DoubleTap      => write!(f, "doubleTap"),
+        }
+    }
+}
+
+impl FromString for Trigger {
+    fn from_string(s: &str) -> Option<Self> {
+        match split_payload(s)? {
+            ("held", None)           => Some(Trigger::Held),
+            ("pressed", None)        => Some(Trigger::Pressed),
+            ("released", None)       => Some(Trigger::Released),
+            ("hold", Some(duration)) => duration.trim().parse().ok().map(Trigger::Hold),
+            ("doubleTap", None)      => Some(Trigger::DoubleTap),
+            _ => None,
+        }
+    }
+}
+
+/// Timing state a `CommandBinding` keeps between frames to evaluate its `Trigger`.
+#[derive(Default)]
+pub struct TriggerState {
+    was_pressed: bool,
+    pressed_at: f32,
+    hold_fired: bool,
+    last_tap: Option<f32>,
+}
+
+impl TriggerState {
+    /// Records whether the binding is pressed at `now` (in seconds) and returns
+    /// whether `trigger` fires this frame.
+    pub fn update(&mut self, trigger: Trigger, pressed: bool, now: f32) -> bool {
+        let just_pressed = pressed && !self.was_pressed;
+        let just_released = !pressed && self.was_pressed;
+        self.was_pressed = pressed;
+
+        if just_pressed {
+            self.pressed_at = now;
+            self.hold_fired = false;
+        }
+
+        match trigger {
+            Trigger::Held => pressed,
+            Trigger::Pressed => just_pressed,
+            Trigger::Released => just_released,
+            Trigger::Hold(duration) => {
+                let fires = pressed && !self.hold_fired && now - self.pressed_at >= duration;
+                self.hold_fired |= fires;
+                fires
+            }
+            Trigger::DoubleTap => {
+                if !just_pressed {
+                    return false;
+                }
+                match self.last_tap {
+                    Some(last_tap) if now - last_tap <= DOUBLE_TAP_INTERVAL => {
+                        self.last_tap = None;
+                        true
+                    }
+                    _ => {
+                        self.last_tap = Some(now);
+                        false
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+pub use rs_rts_macros::SerializeEnum;
+
 pub trait FromString {
     fn from_string(s: &str) -> Option<Self>
     where
@@ -8,14 +10,15 @@ pub trait SerializeEnum: ToString + FromString {
 
 }
 
-pub trait Serialize: ToString + FromString {
-
-}
-
-pub trait Action: SerializeEnum {
-
-}
-
 pub mod camera;
+pub mod cursor;
+pub mod group;
 pub mod input;
-pub mod command;
\ No newline at end of file
+pub mod command;
+pub mod order;
+pub mod pathfinding;
+pub mod selection;
+pub mod unit;
+
+#[cfg(test)]
+pub mod testing;
\ No newline at end of file
@@ -0,0 +1,334 @@
+use crate::core::command::wire::{WireError, WireReader, WireWriter};
+use crate::core::command::{Command, CommandDispatchSet, CommandPayload, PAYLOAD_CLOSE, PAYLOAD_OPEN};
+use bevy::math::Vec2;
+use bevy::prelude::{
+    App, Component, Entity, Event, EventReader, EventWriter, IntoScheduleConfigs, Plugin, Query, SystemSet, Update,
+};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+
+static APPEND: &str = "append";
+static APPEND_SEPARATOR: char = ';';
+
+/// How an order given to an entity joins the orders it already has. As a command
+/// payload, `Append` is written `append` and `Replace` is the default left out, so
+/// bindings choose it as in `unit.holdPosition(append) = Shift + H`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum QueueMode {
+    /// The order replaces the queue and starts right away.
+    #[default]
+    Replace,
+    /// The order starts once the queued ones are completed.
+    Append,
+}
+
+impl CommandPayload for QueueMode {
+    fn default_payload() -> Self {
+        QueueMode::Replace
+    }
+
+    fn parse_payload(payload: Option<&str>) -> Option<Self> {
+        match payload.map(str::trim) {
+            None => Some(QueueMode::Replace),
+            Some(payload) if payload == APPEND => Some(QueueMode::Append),
+            Some(_) => None,
+        }
+    }
+
+    fn write_payload(&self, f: &mut Formatter<'_>, name: &str) -> fmt::Result {
+        match self {
+            QueueMode::Replace => write!(f, "{}", name),
+            QueueMode::Append  => write!(f, "{}{}{}{}", name, PAYLOAD_OPEN, APPEND, PAYLOAD_CLOSE),
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        other
+    }
+
+    fn encode_payload(&self, writer: &mut WireWriter) {
+        writer.write_u8(*self as u8);
+    }
+
+    fn decode_payload(reader: &mut WireReader) -> Result<Self, WireError> {
+        match reader.read_u8()? {
+            0 => Ok(QueueMode::Replace),
+            1 => Ok(QueueMode::Append),
+            tag => Err(WireError::InvalidTag(tag)),
+        }
+    }
+}
+
+/// The payload of an order along with its `QueueMode`, written `; append` after the
+/// payload when the order is appended, as in `unit.move(4, 0, -2; append)`.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Queued<P> {
+    pub value: P,
+    pub mode: QueueMode,
+}
+
+impl<P> Queued<P> {
+    pub fn new(value: P, mode: QueueMode) -> Self {
+        Self { value, mode }
+    }
+}
+
+impl<P> From<P> for Queued<P> {
+    /// The payload of an order replacing the queue.
+    fn from(value: P) -> Self {
+        Self::new(value, QueueMode::Replace)
+    }
+}
+
+/// Writes a payload as `CommandPayload::write_payload` does.
+struct Written<'a, P>(&'a P, &'a str);
+
+impl<P> Display for Written<'_, P>
+where
+    P: CommandPayload,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.write_payload(f, self.1)
+    }
+}
+
+impl<P> CommandPayload for Queued<P>
+where
+    P: CommandPayload,
+{
+    fn default_payload() -> Self {
+        P::default_payload().into()
+    }
+
+    fn parse_payload(payload: Option<&str>) -> Option<Self> {
+        let Some(payload) = payload.map(str::trim) else {
+            return P::parse_payload(None).map(Self::from);
+        };
+        if payload == APPEND {
+            return P::parse_payload(None).map(|value| Self::new(value, QueueMode::Append));
+        }
+
+        match payload.strip_suffix(APPEND).and_then(|rest| rest.trim_end().strip_suffix(APPEND_SEPARATOR)) {
+            Some(value) => P::parse_payload(Some(value)).map(|value| Self::new(value, QueueMode::Append)),
+            None => P::parse_payload(Some(payload)).map(Self::from),
+        }
+    }
+
+    fn write_payload(&self, f: &mut Formatter<'_>, name: &str) -> fmt::Result {
+        if self.mode == QueueMode::Replace {
+            return self.value.write_payload(f, name);
+        }
+
+        let written = Written(&self.value, name).to_string();
+        match written.strip_suffix(PAYLOAD_CLOSE) {
+            Some(open) => write!(f, "{}{} {}{}", open, APPEND_SEPARATOR, APPEND, PAYLOAD_CLOSE),
+            None => write!(f, "{}{}{}{}", written, PAYLOAD_OPEN, APPEND, PAYLOAD_CLOSE),
+        }
+    }
+
+    fn with_axis(self, axis: Vec2) -> Self {
+        Self::new(self.value.with_axis(axis), self.mode)
+    }
+
+    fn merge(self, other: Self) -> Self {
+        Self::new(self.value.merge(other.value), other.mode)
+    }
+
+    fn encode_payload(&self, writer: &mut WireWriter) {
+        self.value.encode_payload(writer);
+        self.mode.encode_payload(writer);
+    }
+
+    fn decode_payload(reader: &mut WireReader) -> Result<Self, WireError> {
+        Ok(Self::new(P::decode_payload(reader)?, QueueMode::decode_payload(reader)?))
+    }
+}
+
+/// The orders an entity carries out one after the other, the front one being under
+/// way. Any `Command` type can be queued, so units, builders and transports share
+/// the same queueing.
+///
+/// The `OrderQueuePlugin` sends an `OrderStarted` event when an order reaches the
+/// front of the queue, and pops it on the `OrderCompleted` event sent by the system
+/// carrying it out.
+#[derive(Component)]
+pub struct OrderQueue<C> {
+    orders: VecDeque<C>,
+    /// Whether the front order was started. A completion coming after the queue was
+    /// replaced belongs to the replaced order and is ignored.
+    started: bool,
+}
+
+impl<C> Default for OrderQueue<C> {
+    fn default() -> Self {
+        Self { orders: VecDeque::new(), started: false }
+    }
+}
+
+impl<C> OrderQueue<C> {
+    pub fn push(&mut self, mode: QueueMode, order: C) {
+        if mode == QueueMode::Replace {
+            self.clear();
+        }
+        self.orders.push_back(order);
+    }
+
+    pub fn clear(&mut self) {
+        self.orders.clear();
+        self.started = false;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &C> {
+        self.orders.iter()
+    }
+
+    /// Whether the front order still has to be started.
+    fn is_waiting(&self) -> bool {
+        !self.started && !self.orders.is_empty()
+    }
+
+    /// Pops the front order, if it was started.
+    fn complete(&mut self) -> Option<C> {
+        if !self.started {
+            return None;
+        }
+        self.started = false;
+        self.orders.pop_front()
+    }
+}
+
+/// Sent when `order` reaches the front of the `OrderQueue` of `entity`.
+#[derive(Event, Clone, Debug)]
+pub struct OrderStarted<C: Command> {
+    pub entity: Entity,
+    pub order: C,
+}
+
+/// Sent by the system carrying out the current `C` order of `entity` once it is done,
+/// for the next one to start.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct OrderCompleted<C: Command> {
+    pub entity: Entity,
+    _marker: PhantomData<C>,
+}
+
+impl<C: Command> OrderCompleted<C> {
+    pub fn new(entity: Entity) -> Self {
+        Self { entity, _marker: PhantomData }
+    }
+}
+
+/// Order queues advance in this set, after the commands are dispatched. Orders are
+/// pushed before it and carried out after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OrderQueueSet;
+
+/// Advances the `OrderQueue<C>` components, sending `OrderStarted<C>` events and
+/// reading `OrderCompleted<C>` ones.
+pub struct OrderQueuePlugin<C> {
+    _marker: PhantomData<C>,
+}
+
+impl<C> Default for OrderQueuePlugin<C> {
+    fn default() -> Self {
+        Self { _marker: PhantomData }
+    }
+}
+
+impl<C> Plugin for OrderQueuePlugin<C>
+where
+    C: Command + Clone,
+{
+    fn build(&self, app: &mut App) {
+        app.add_event::<OrderStarted<C>>()
+            .add_event::<OrderCompleted<C>>()
+            .configure_sets(Update, OrderQueueSet.after(CommandDispatchSet))
+            .add_systems(Update, advance_order_queues::<C>.in_set(OrderQueueSet));
+    }
+}
+
+fn advance_order_queues<C: Command + Clone>(
+    mut completed: EventReader<OrderCompleted<C>>,
+    mut started: EventWriter<OrderStarted<C>>,
+    mut queues: Query<(Entity, &mut OrderQueue<C>)>,
+) {
+    for event in completed.read() {
+        if let Ok((_, mut queue)) = queues.get_mut(event.entity) {
+            queue.complete();
+        }
+    }
+
+    for (entity, mut queue) in &mut queues {
+        if queue.is_waiting() {
+            queue.started = true;
+            let order = queue.orders[0].clone();
+            started.write(OrderStarted { entity, order });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::TestApp;
+    use crate::core::unit::UnitCommand;
+    use bevy::math::Vec3;
+    use bevy::prelude::{Events, World};
+
+    fn move_to(x: f32) -> UnitCommand {
+        UnitCommand::Move { target: Vec3::new(x, 0.0, 0.0).into() }
+    }
+
+    fn started(world: &mut World) -> Vec<UnitCommand> {
+        let mut events = world.resource_mut::<Events<OrderStarted<UnitCommand>>>();
+        events.drain().map(|event| event.order).collect()
+    }
+
+    fn complete(world: &mut World, entity: Entity) {
+        world.send_event(OrderCompleted::<UnitCommand>::new(entity));
+    }
+
+    #[test]
+    fn completions_start_the_next_order() {
+        let mut app = TestApp::new(OrderQueuePlugin::<UnitCommand>::default());
+        let entity = app.world_mut().spawn(OrderQueue::<UnitCommand>::default()).id();
+        let mut queue = app.world_mut().get_mut::<OrderQueue<UnitCommand>>(entity).unwrap();
+        queue.push(QueueMode::Append, move_to(1.0));
+        queue.push(QueueMode::Append, move_to(2.0));
+        app.update();
+        assert_eq!(started(app.world_mut()), [move_to(1.0)]);
+
+        app.update();
+        assert!(started(app.world_mut()).is_empty());
+
+        complete(app.world_mut(), entity);
+        app.update();
+        assert_eq!(started(app.world_mut()), [move_to(2.0)]);
+
+        complete(app.world_mut(), entity);
+        app.update();
+        assert!(started(app.world_mut()).is_empty());
+        assert!(app.world().get::<OrderQueue<UnitCommand>>(entity).unwrap().orders.is_empty());
+    }
+
+    #[test]
+    fn replacing_ignores_the_completion_of_the_replaced_order() {
+        let mut app = TestApp::new(OrderQueuePlugin::<UnitCommand>::default());
+        let entity = app.world_mut().spawn(OrderQueue::<UnitCommand>::default()).id();
+        let mut queue = app.world_mut().get_mut::<OrderQueue<UnitCommand>>(entity).unwrap();
+        queue.push(QueueMode::Replace, move_to(1.0));
+        app.update();
+        started(app.world_mut());
+
+        // The first order completes while it is being replaced
+        complete(app.world_mut(), entity);
+        let mut queue = app.world_mut().get_mut::<OrderQueue<UnitCommand>>(entity).unwrap();
+        queue.push(QueueMode::Replace, move_to(3.0));
+        queue.push(QueueMode::Append, move_to(4.0));
+        app.update();
+        assert_eq!(started(app.world_mut()), [move_to(3.0)]);
+        assert_eq!(app.world().get::<OrderQueue<UnitCommand>>(entity).unwrap().orders.len(), 2);
+    }
+}
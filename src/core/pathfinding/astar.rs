@@ -0,0 +1,181 @@
+use crate::core::pathfinding::grid::{NavGrid, DEFAULT_COST, DIAGONAL_STEP, STRAIGHT_STEP};
+use bevy::math::IVec2;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+const NO_PARENT: u32 = u32::MAX;
+
+/// A* searches over a `NavGrid`, keeping their buffers from one search to the next
+/// so planning the paths of hundreds of units does not allocate for every one.
+#[derive(Default)]
+pub struct PathFinder {
+    /// Cost from the start of the cells reached by the search stamped in `reached`.
+    costs: Vec<u32>,
+    parents: Vec<u32>,
+    /// The search each cell was last reached by, sparing a clear of the buffers.
+    reached: Vec<u32>,
+    search: u32,
+    /// Cells to expand by estimated total cost, then estimated remaining cost.
+    open: BinaryHeap<Reverse<(u32, u32, u32)>>,
+}
+
+impl PathFinder {
+    /// The cheapest cells from `start` to `goal`, both included, or `None` if `goal`
+    /// cannot be reached. The start may be blocked, letting a unit caught in a new
+    /// obstacle walk out of it.
+    pub fn find_path(&mut self, grid: &NavGrid, start: IVec2, goal: IVec2) -> Option<Vec<IVec2>> {
+        let (start_index, goal_index) = (grid.index(start)?, grid.index(goal)?);
+        if !grid.is_walkable(goal) {
+            return None;
+        }
+        self.reset(grid);
+
+        self.reach(start_index, 0, NO_PARENT);
+        self.open.push(Reverse((heuristic(start, goal), heuristic(start, goal), start_index as u32)));
+        while let Some(Reverse((estimate, remaining, index))) = self.open.pop() {
+            let index = index as usize;
+            if index == goal_index {
+                return Some(self.walk_back(grid, goal_index));
+            }
+            // Skip the entries left behind when a cheaper way to the cell was found
+            let cost = estimate - remaining;
+            if cost > self.costs[index] {
+                continue;
+            }
+
+            let cell = grid.cell(index);
+            for (neighbour, step) in grid.neighbours(cell) {
+                let neighbour_index = grid.index(neighbour).expect("walkable cells are in the grid");
+                let cost = cost + step;
+                if self.reached[neighbour_index] != self.search || cost < self.costs[neighbour_index] {
+                    self.reach(neighbour_index, cost, index as u32);
+                    let remaining = heuristic(neighbour, goal);
+                    self.open.push(Reverse((cost + remaining, remaining, neighbour_index as u32)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Like `find_path`, keeping only the cells where the path turns, each in sight of
+    /// the previous one.
+    pub fn find_smooth_path(&mut self, grid: &NavGrid, start: IVec2, goal: IVec2) -> Option<Vec<IVec2>> {
+        self.find_path(grid, start, goal).map(|path| smooth(grid, &path))
+    }
+
+    fn reset(&mut self, grid: &NavGrid) {
+        let cells = (grid.size().x * grid.size().y) as usize;
+        if self.reached.len() != cells {
+            self.costs = vec![0; cells];
+            self.parents = vec![NO_PARENT; cells];
+            self.reached = vec![0; cells];
+            self.search = 0;
+        }
+        self.search = self.search.wrapping_add(1);
+        if self.search == 0 {
+            self.reached.fill(0);
+            self.search = 1;
+        }
+        self.open.clear();
+    }
+
+    fn reach(&mut self, index: usize, cost: u32, parent: u32) {
+        self.costs[index] = cost;
+        self.parents[index] = parent;
+        self.reached[index] = self.search;
+    }
+
+    fn walk_back(&self, grid: &NavGrid, goal: usize) -> Vec<IVec2> {
+        let mut path = vec![grid.cell(goal)];
+        let mut index = goal;
+        while self.parents[index] != NO_PARENT {
+            index = self.parents[index] as usize;
+            path.push(grid.cell(index));
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// The cost of the shortest way from `from` to `to` on ground of the lowest cost,
+/// never more than the real cost so the search stays exact.
+pub fn heuristic(from: IVec2, to: IVec2) -> u32 {
+    let delta = (to - from).abs();
+    let (straight, diagonal) = ((delta.x - delta.y).unsigned_abs(), delta.x.min(delta.y) as u32);
+    (straight * STRAIGHT_STEP + diagonal * DIAGONAL_STEP) * DEFAULT_COST as u32
+}
+
+/// Drops the cells of `path` a unit can walk past in a straight line, keeping its
+/// ends.
+pub fn smooth(grid: &NavGrid, path: &[IVec2]) -> Vec<IVec2> {
+    let Some((&first, rest)) = path.split_first() else {
+        return Vec::new();
+    };
+
+    let mut smoothed = vec![first];
+    let mut anchor = first;
+    for (index, &cell) in rest.iter().enumerate() {
+        let next = rest.get(index + 1);
+        if next.is_none_or(|&next| !grid.line_of_sight(anchor, next)) {
+            smoothed.push(cell);
+            anchor = cell;
+        }
+    }
+    smoothed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::pathfinding::grid::BLOCKED;
+    use crate::core::pathfinding::synthetic;
+
+    fn cost(grid: &NavGrid, path: &[IVec2]) -> u32 {
+        path.windows(2).map(|step| grid.step_cost(step[0], step[1] - step[0]).unwrap()).sum()
+    }
+
+    #[test]
+    fn paths_go_around_walls() {
+        let grid = synthetic::walls(16);
+        let mut finder = PathFinder::default();
+        let path = finder.find_path(&grid, IVec2::new(0, 0), IVec2::new(15, 15)).unwrap();
+
+        assert_eq!(path.first(), Some(&IVec2::new(0, 0)));
+        assert_eq!(path.last(), Some(&IVec2::new(15, 15)));
+        assert!(path.iter().all(|&cell| grid.is_walkable(cell)));
+        assert!(path.windows(2).all(|step| grid.step_cost(step[0], step[1] - step[0]).is_some()));
+
+        let smoothed = smooth(&grid, &path);
+        assert!(smoothed.len() < path.len());
+        assert!(smoothed.windows(2).all(|segment| grid.line_of_sight(segment[0], segment[1])));
+    }
+
+    #[test]
+    fn paths_are_the_cheapest() {
+        let mut grid = synthetic::open(8);
+        let mut finder = PathFinder::default();
+        let straight = finder.find_path(&grid, IVec2::new(0, 3), IVec2::new(7, 3)).unwrap();
+        assert_eq!(cost(&grid, &straight), 70);
+        assert_eq!(smooth(&grid, &straight), [IVec2::new(0, 3), IVec2::new(7, 3)]);
+
+        // A swamp across the straight way is cheaper to walk around
+        (2..6).for_each(|y| grid.set_cost(IVec2::new(4, y), 5));
+        let around = finder.find_path(&grid, IVec2::new(0, 3), IVec2::new(7, 3)).unwrap();
+        assert!(around.iter().all(|&cell| grid.cost(cell) == DEFAULT_COST));
+        assert_eq!(cost(&grid, &around), 3 * STRAIGHT_STEP + 4 * DIAGONAL_STEP);
+    }
+
+    #[test]
+    fn unreachable_goals_have_no_path() {
+        let mut grid = synthetic::open(8);
+        let mut finder = PathFinder::default();
+        (0..8).for_each(|y| grid.set_cost(IVec2::new(4, y), BLOCKED));
+        assert_eq!(finder.find_path(&grid, IVec2::new(0, 0), IVec2::new(7, 7)), None);
+        assert_eq!(finder.find_path(&grid, IVec2::new(0, 0), IVec2::new(4, 4)), None);
+        assert_eq!(finder.find_path(&grid, IVec2::new(0, 0), IVec2::new(9, 0)), None);
+
+        // Out of a blocked start, with the buffers of the previous searches
+        let path = finder.find_path(&grid, IVec2::new(4, 0), IVec2::new(0, 0)).unwrap();
+        assert_eq!(path.len(), 5);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::core::pathfinding::grid::{NavGrid, NEIGHBOURS};
+use bevy::math::IVec2;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+const UNREACHED: u32 = u32::MAX;
+const NO_DIRECTION: u8 = u8::MAX;
+
+/// The way to one goal from every cell of a `NavGrid`, computed once and shared by
+/// all the units heading there, however many they are.
+///
+/// Every cell the goal can be reached from holds the cost to the goal and the
+/// neighbour to step to, one of the `NEIGHBOURS`.
+#[derive(Clone, Debug)]
+pub struct FlowField {
+    goal: IVec2,
+    /// Version of the `NavGrid` the field was computed on.
+    version: u32,
+    size: IVec2,
+    costs: Vec<u32>,
+    directions: Vec<u8>,
+}
+
+impl FlowField {
+    /// Runs Dijkstra's algorithm back from `goal` over the whole grid.
+    pub fn new(grid: &NavGrid, goal: IVec2) -> Self {
+        let cells = (grid.size().x * grid.size().y) as usize;
+        let mut field = Self {
+            goal,
+            version: grid.version(),
+            size: grid.size(),
+            costs: vec![UNREACHED; cells],
+            directions: vec![NO_DIRECTION; cells],
+        };
+        let Some(goal_index) = grid.index(goal).filter(|_| grid.is_walkable(goal)) else {
+            return field;
+        };
+
+        // Steps are as costly both ways, the cost of a cell being paid on entering it,
+        // so the search expands from the goal with the cost of leaving each cell
+        let mut open = BinaryHeap::new();
+        field.costs[goal_index] = 0;
+        open.push(Reverse((0, goal_index as u32)));
+        while let Some(Reverse((cost, index))) = open.pop() {
+            let index = index as usize;
+            if cost > field.costs[index] {
+                continue;
+            }
+
+            let cell = grid.cell(index);
+            for (direction, &offset) in NEIGHBOURS.iter().enumerate() {
+                let neighbour = cell + offset;
+                let (Some(neighbour_index), Some(step)) = (grid.index(neighbour), grid.step_cost(neighbour, -offset))
+                else {
+                    continue;
+                };
+                if !grid.is_walkable(neighbour) {
+                    continue;
+                }
+
+                let cost = cost + step;
+                if cost < field.costs[neighbour_index] {
+                    field.costs[neighbour_index] = cost;
+                    field.directions[neighbour_index] = opposite(direction);
+                    open.push(Reverse((cost, neighbour_index as u32)));
+                }
+            }
+        }
+        field
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn index(&self, cell: IVec2) -> Option<usize> {
+        (cell.cmpge(IVec2::ZERO).all() && cell.cmplt(self.size).all()).then(|| (cell.y * self.size.x + cell.x) as usize)
+    }
+
+    /// The cost of the way from `cell` to the goal, if there is one.
+    pub fn cost(&self, cell: IVec2) -> Option<u32> {
+        self.index(cell).map(|index| self.costs[index]).filter(|&cost| cost != UNREACHED)
+    }
+
+    /// The cell to step to from `cell` on the way to the goal. From a cell the field
+    /// does not cover, such as one a new obstacle was put on, it is the neighbour
+    /// closest to the goal.
+    pub fn next_cell(&self, cell: IVec2) -> Option<IVec2> {
+        if cell == self.goal {
+            return None;
+        }
+        match self.index(cell).map(|index| self.directions[index]) {
+            Some(direction) if direction != NO_DIRECTION => Some(cell + NEIGHBOURS[direction as usize]),
+            _ => NEIGHBOURS
+                .iter()
+                .map(|&offset| cell + offset)
+                .filter_map(|neighbour| self.cost(neighbour).map(|cost| (cost, neighbour)))
+                .min_by_key(|&(cost, _)| cost)
+                .map(|(_, neighbour)| neighbour),
+        }
+    }
+}
+
+/// The index in `NEIGHBOURS` of the offset opposite to the one at `direction`.
+fn opposite(direction: usize) -> u8 {
+    let offset = -NEIGHBOURS[direction];
+    NEIGHBOURS.iter().position(|&other| other == offset).expect("every neighbour has an opposite") as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::pathfinding::astar::PathFinder;
+    use crate::core::pathfinding::grid::BLOCKED;
+    use crate::core::pathfinding::synthetic;
+
+    fn follow(field: &FlowField, mut cell: IVec2) -> Vec<IVec2> {
+        let mut cells = vec![cell];
+        while let Some(next) = field.next_cell(cell) {
+            cell = next;
+            cells.push(cell);
+        }
+        cells
+    }
+
+    #[test]
+    fn fields_cost_as_much_as_the_cheapest_paths() {
+        let mut grid = synthetic::random(32, 0.3, 7);
+        grid.set_cost(IVec2::new(20, 20), 1);
+        (10..14).for_each(|x| grid.set_cost(IVec2::new(x, 5), 4));
+        let field = FlowField::new(&grid, IVec2::new(20, 20));
+        let mut finder = PathFinder::default();
+
+        let mut reached = 0;
+        for index in 0..32 * 32 {
+            let start = grid.cell(index);
+            let path = finder.find_path(&grid, start, IVec2::new(20, 20)).filter(|_| grid.is_walkable(start));
+            let cost = |cells: &[IVec2]| -> u32 {
+                cells.windows(2).map(|step| grid.step_cost(step[0], step[1] - step[0]).unwrap()).sum()
+            };
+            match path {
+                Some(path) => {
+                    reached += 1;
+                    assert_eq!(field.cost(start), Some(cost(&path)), "from {}", start);
+                    assert_eq!(cost(&follow(&field, start)), cost(&path), "from {}", start);
+                }
+                None => assert_eq!(field.cost(start), None, "from {}", start),
+            }
+        }
+        assert!(reached > 32 * 32 / 2);
+    }
+
+    #[test]
+    fn blocked_cells_lead_out_to_the_field() {
+        let mut grid = synthetic::open(8);
+        grid.set_cost(IVec2::new(3, 3), BLOCKED);
+        let field = FlowField::new(&grid, IVec2::new(7, 3));
+        assert_eq!(field.cost(IVec2::new(3, 3)), None);
+        assert_eq!(field.next_cell(IVec2::new(3, 3)), Some(IVec2::new(4, 3)));
+        assert_eq!(follow(&field, IVec2::new(7, 3)), [IVec2::new(7, 3)]);
+
+        let unreachable = FlowField::new(&grid, IVec2::new(3, 3));
+        assert_eq!(unreachable.next_cell(IVec2::new(0, 0)), None);
+    }
+}
@@ -0,0 +1,311 @@
+use bevy::math::{IVec2, Rect, Vec2, Vec3};
+use bevy::prelude::Resource;
+
+/// Cost of a cell units cannot cross.
+pub const BLOCKED: u8 = u8::MAX;
+/// Cost of a cell of plain ground.
+pub const DEFAULT_COST: u8 = 1;
+
+/// Cost of a straight step between two cells of cost 1, a diagonal one costing
+/// `DIAGONAL_STEP`. Integer costs keep the searches exact and repeatable.
+pub const STRAIGHT_STEP: u32 = 10;
+pub const DIAGONAL_STEP: u32 = 14;
+
+/// The eight neighbours of a cell, the straight ones first.
+pub const NEIGHBOURS: [IVec2; 8] = [
+    IVec2::new(1, 0),
+    IVec2::new(-1, 0),
+    IVec2::new(0, 1),
+    IVec2::new(0, -1),
+    IVec2::new(1, 1),
+    IVec2::new(-1, 1),
+    IVec2::new(1, -1),
+    IVec2::new(-1, -1),
+];
+
+/// The ground split into square cells, each with the cost of crossing it from 1 to
+/// 254, or `BLOCKED`.
+///
+/// Cell `(x, y)` covers the world `x` and `z` from `origin + (x, y) * cell_size`, one
+/// `cell_size` wide and deep. Cells outside the grid are blocked.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct NavGrid {
+    origin: Vec2,
+    cell_size: f32,
+    size: IVec2,
+    costs: Vec<u8>,
+    /// Bumped whenever the costs change, telling the routes planned on older grids.
+    version: u32,
+}
+
+impl NavGrid {
+    /// A grid of `size` blocked cells, with the corner of cell `(0, 0)` at `origin`.
+    pub fn new(origin: Vec2, cell_size: f32, size: IVec2) -> Self {
+        let size = size.max(IVec2::ZERO);
+        Self { origin, cell_size, size, costs: vec![BLOCKED; (size.x * size.y) as usize], version: 0 }
+    }
+
+    /// A grid of blocked cells covering `bounds`, on the world `x` and `z`.
+    pub fn covering(bounds: Rect, cell_size: f32) -> Self {
+        let size = (bounds.size() / cell_size).ceil().as_ivec2();
+        Self::new(bounds.min, cell_size, size)
+    }
+
+    pub fn size(&self) -> IVec2 {
+        self.size
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.costs.is_empty()
+    }
+
+    pub fn contains(&self, cell: IVec2) -> bool {
+        cell.cmpge(IVec2::ZERO).all() && cell.cmplt(self.size).all()
+    }
+
+    /// The position of `cell` in row-major order, if it is in the grid.
+    pub fn index(&self, cell: IVec2) -> Option<usize> {
+        self.contains(cell).then(|| (cell.y * self.size.x + cell.x) as usize)
+    }
+
+    /// The cell at `index` in row-major order.
+    pub fn cell(&self, index: usize) -> IVec2 {
+        IVec2::new(index as i32 % self.size.x, index as i32 / self.size.x)
+    }
+
+    pub fn cost(&self, cell: IVec2) -> u8 {
+        self.index(cell).map_or(BLOCKED, |index| self.costs[index])
+    }
+
+    pub fn is_walkable(&self, cell: IVec2) -> bool {
+        self.cost(cell) != BLOCKED
+    }
+
+    #[cfg(test)]
+    pub fn set_cost(&mut self, cell: IVec2, cost: u8) {
+        if let Some(index) = self.index(cell)
+            && self.costs[index] != cost
+        {
+            self.costs[index] = cost;
+            self.version += 1;
+        }
+    }
+
+    /// Sets the cost of every cell overlapping `area`, on the world `x` and `z`. The
+    /// part of `area` outside the grid is ignored.
+    pub fn fill(&mut self, area: Rect, cost: u8) {
+        let min = ((area.min - self.origin) / self.cell_size).floor().as_ivec2().max(IVec2::ZERO);
+        let max = ((area.max - self.origin) / self.cell_size).ceil().as_ivec2().min(self.size);
+        if min.x >= max.x || min.y >= max.y {
+            return;
+        }
+
+        let mut changed = false;
+        for y in min.y..max.y {
+            let row = (y * self.size.x) as usize;
+            for cell in &mut self.costs[row + min.x as usize..row + max.x as usize] {
+                changed |= *cell != cost;
+                *cell = cost;
+            }
+        }
+        if changed {
+            self.version += 1;
+        }
+    }
+
+    /// Takes the cells of `other`, as a newer version of this grid.
+    pub fn replace(&mut self, other: NavGrid) {
+        let version = self.version.max(other.version) + 1;
+        *self = NavGrid { version, ..other };
+    }
+
+    /// The cell containing `point`, which may be outside the grid.
+    pub fn cell_at(&self, point: Vec3) -> IVec2 {
+        ((Vec2::new(point.x, point.z) - self.origin) / self.cell_size).floor().as_ivec2()
+    }
+
+    /// The centre of `cell`, on the ground.
+    pub fn center(&self, cell: IVec2) -> Vec3 {
+        let center = self.origin + (cell.as_vec2() + 0.5) * self.cell_size;
+        Vec3::new(center.x, 0.0, center.y)
+    }
+
+    /// The cost of stepping from `cell` by `offset`, one of the `NEIGHBOURS`, or `None`
+    /// if the step is blocked. Diagonal steps cannot cut the corner of a blocked cell.
+    pub fn step_cost(&self, cell: IVec2, offset: IVec2) -> Option<u32> {
+        let cost = self.cost(cell + offset);
+        if cost == BLOCKED {
+            return None;
+        }
+        if offset.x == 0 || offset.y == 0 {
+            return Some(STRAIGHT_STEP * cost as u32);
+        }
+
+        let corners = [IVec2::new(cell.x + offset.x, cell.y), IVec2::new(cell.x, cell.y + offset.y)];
+        corners.iter().all(|&corner| self.is_walkable(corner)).then_some(DIAGONAL_STEP * cost as u32)
+    }
+
+    /// The walkable neighbours of `cell` with the cost of stepping to them.
+    pub fn neighbours(&self, cell: IVec2) -> impl Iterator<Item = (IVec2, u32)> + '_ {
+        NEIGHBOURS.iter().filter_map(move |&offset| self.step_cost(cell, offset).map(|cost| (cell + offset, cost)))
+    }
+
+    /// The walkable cell closest to `cell`, searching rings around it up to `radius`
+    /// cells away.
+    pub fn nearest_walkable(&self, cell: IVec2, radius: i32) -> Option<IVec2> {
+        if self.is_walkable(cell) {
+            return Some(cell);
+        }
+
+        (1..=radius).find_map(|ring| {
+            (-ring..=ring)
+                .flat_map(|dy| (-ring..=ring).map(move |dx| IVec2::new(dx, dy)))
+                .filter(|offset| offset.x.abs() == ring || offset.y.abs() == ring)
+                .map(|offset| cell + offset)
+                .filter(|&candidate| self.is_walkable(candidate))
+                .min_by_key(|&candidate| (candidate - cell).length_squared())
+        })
+    }
+
+    /// Whether a unit can walk straight from the centre of `from` to the centre of
+    /// `to`, through cells no more costly than `from`.
+    ///
+    /// The cells the segment crosses are walked one after the other, and a segment
+    /// going exactly through the corner of four cells needs both cells beside it
+    /// walkable, as diagonal steps do.
+    pub fn line_of_sight(&self, from: IVec2, to: IVec2) -> bool {
+        let max_cost = self.cost(from).max(DEFAULT_COST);
+        let crossable = |cell: IVec2| self.cost(cell) <= max_cost && self.is_walkable(cell);
+
+        let delta = to - from;
+        let step = delta.signum();
+        let span = delta.abs();
+        let mut cell = from;
+        // Progress along the segment times `2 * span.x * span.y`, to stay in integers
+        let (mut next_x, mut next_y) = (span.y as i64, span.x as i64);
+        let (step_x, step_y) = (2 * span.y as i64, 2 * span.x as i64);
+
+        while cell != to {
+            if span.x != 0 && (span.y == 0 || next_x < next_y) {
+                cell.x += step.x;
+                next_x += step_x;
+            } else if span.y != 0 && (span.x == 0 || next_y < next_x) {
+                cell.y += step.y;
+                next_y += step_y;
+            } else {
+                // Through a corner
+                if !crossable(IVec2::new(cell.x + step.x, cell.y)) || !crossable(IVec2::new(cell.x, cell.y + step.y)) {
+                    return false;
+                }
+                cell += step;
+                next_x += step_x;
+                next_y += step_y;
+            }
+
+            if !crossable(cell) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> NavGrid {
+        let mut grid = NavGrid::covering(Rect::new(-5.0, -5.0, 5.0, 5.0), 0.5);
+        grid.fill(Rect::new(-5.0, -5.0, 5.0, 5.0), DEFAULT_COST);
+        grid
+    }
+
+    #[test]
+    fn footprints_block_the_cells_they_overlap() {
+        let mut grid = grid();
+        assert_eq!(grid.size(), IVec2::new(20, 20));
+        assert_eq!(grid.cell_at(Vec3::new(0.1, 3.0, -0.1)), IVec2::new(10, 9));
+        assert_eq!(grid.center(IVec2::new(10, 9)), Vec3::new(0.25, 0.0, -0.25));
+
+        let version = grid.version();
+        grid.fill(Rect::new(-0.6, -0.5, 0.5, 0.5), BLOCKED);
+        assert!(grid.version() > version);
+        let blocked: Vec<IVec2> =
+            (0..400).map(|index| grid.cell(index)).filter(|&cell| !grid.is_walkable(cell)).collect();
+        assert_eq!(
+            blocked,
+            [
+                IVec2::new(8, 9),
+                IVec2::new(9, 9),
+                IVec2::new(10, 9),
+                IVec2::new(8, 10),
+                IVec2::new(9, 10),
+                IVec2::new(10, 10)
+            ]
+        );
+        assert!(!grid.is_walkable(IVec2::new(-1, 0)));
+        assert!(!grid.is_walkable(IVec2::new(0, 20)));
+    }
+
+    #[test]
+    fn footprints_outside_the_grid_change_nothing() {
+        let mut grid = grid();
+        let version = grid.version();
+        grid.fill(Rect::new(8.0, -1.0, 9.0, 1.0), BLOCKED);
+        grid.fill(Rect::new(-9.0, -1.0, -8.0, 1.0), BLOCKED);
+        grid.fill(Rect::new(-1.0, 6.0, 1.0, 7.0), BLOCKED);
+        grid.fill(Rect::new(-5.0, -5.0, 5.0, 5.0), DEFAULT_COST);
+        assert_eq!(grid.version(), version);
+        assert!((0..400).all(|index| grid.is_walkable(grid.cell(index))));
+
+        // Overlapping an edge blocks the cells inside
+        grid.fill(Rect::new(4.6, -0.4, 7.0, 0.4), BLOCKED);
+        assert!(grid.version() > version);
+        assert!(!grid.is_walkable(IVec2::new(19, 9)));
+        assert!(grid.is_walkable(IVec2::new(18, 9)));
+    }
+
+    #[test]
+    fn diagonals_do_not_cut_corners() {
+        let mut grid = grid();
+        grid.set_cost(IVec2::new(1, 0), BLOCKED);
+        let neighbours: Vec<IVec2> = grid.neighbours(IVec2::new(0, 0)).map(|(cell, _)| cell).collect();
+        assert_eq!(neighbours, [IVec2::new(0, 1)]);
+
+        grid.set_cost(IVec2::new(5, 6), 3);
+        let costs: Vec<u32> = grid.neighbours(IVec2::new(5, 5)).map(|(_, cost)| cost).collect();
+        assert_eq!(costs, [10, 10, 30, 10, 14, 14, 14, 14]);
+    }
+
+    #[test]
+    fn line_of_sight_walks_the_crossed_cells() {
+        let mut grid = grid();
+        assert!(grid.line_of_sight(IVec2::new(0, 0), IVec2::new(19, 7)));
+        grid.set_cost(IVec2::new(10, 4), BLOCKED);
+        assert!(!grid.line_of_sight(IVec2::new(0, 0), IVec2::new(19, 7)));
+        assert!(grid.line_of_sight(IVec2::new(0, 0), IVec2::new(19, 0)));
+
+        // Exactly through the corner between a blocked and a free cell
+        grid.set_cost(IVec2::new(3, 2), BLOCKED);
+        assert!(!grid.line_of_sight(IVec2::new(2, 2), IVec2::new(3, 3)));
+        assert!(grid.line_of_sight(IVec2::new(2, 2), IVec2::new(1, 3)));
+
+        // Costly ground is only crossed from ground as costly
+        grid.set_cost(IVec2::new(5, 15), 5);
+        assert!(!grid.line_of_sight(IVec2::new(0, 15), IVec2::new(9, 15)));
+        assert!(grid.line_of_sight(IVec2::new(5, 15), IVec2::new(9, 15)));
+    }
+
+    #[test]
+    fn nearest_walkable_searches_rings() {
+        let mut grid = grid();
+        grid.fill(Rect::new(-1.0, -1.0, 1.0, 1.0), BLOCKED);
+        assert_eq!(grid.nearest_walkable(IVec2::new(10, 10), 1), None);
+        assert_eq!(grid.nearest_walkable(IVec2::new(10, 10), 3), Some(IVec2::new(12, 10)));
+        assert_eq!(grid.nearest_walkable(IVec2::new(3, 3), 3), Some(IVec2::new(3, 3)));
+    }
+}
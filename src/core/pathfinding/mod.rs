@@ -0,0 +1,615 @@
+use bevy::math::{IVec2, Rect, Vec2, Vec3};
+use bevy::prelude::{
+    App, Changed, Component, DetectChanges, Entity, IntoScheduleConfigs, Or, Plugin, Query, RemovedComponents, Res,
+    ResMut, Resource, SystemSet, Transform, Update,
+};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+pub mod astar;
+pub mod flow;
+pub mod grid;
+#[cfg(test)]
+pub mod synthetic;
+
+use astar::PathFinder;
+use flow::FlowField;
+use grid::{NavGrid, BLOCKED, DEFAULT_COST};
+
+const DEFAULT_CELL_SIZE: f32 = 0.5;
+const DEFAULT_CLEARANCE: f32 = 0.2;
+const DEFAULT_FLOW_FIELD_AGENTS: usize = 8;
+const DEFAULT_MAX_SEARCHES: usize = 32;
+/// Cells searched around a blocked destination for the closest walkable one.
+const GOAL_SEARCH_RADIUS: i32 = 8;
+/// Distance at which a waypoint counts as reached.
+const WAYPOINT_RADIUS: f32 = 1e-3;
+
+/// Ground units can walk on, `size` wide and deep around the position of its
+/// `Transform`, costing `cost` to cross.
+#[derive(Component, Clone, Copy)]
+pub struct NavTerrain {
+    pub size: Vec2,
+    pub cost: u8,
+}
+
+impl NavTerrain {
+    pub fn new(size: Vec2) -> Self {
+        Self { size, cost: DEFAULT_COST }
+    }
+}
+
+/// The footprint of something units walk around, such as a building, `size` wide and
+/// deep around the position of its `Transform`. The `NavGrid` is rebuilt when one is
+/// added, changed or removed, not when it moves.
+#[derive(Component, Clone, Copy)]
+pub struct Obstacle {
+    pub size: Vec2,
+}
+
+impl Obstacle {
+    pub fn new(size: Vec2) -> Self {
+        Self { size }
+    }
+}
+
+fn footprint(transform: &Transform, size: Vec2) -> Rect {
+    Rect::from_center_size(Vec2::new(transform.translation.x, transform.translation.z), size)
+}
+
+#[derive(Resource, Clone, Copy)]
+pub struct PathfindingSettings {
+    /// Width of the `NavGrid` cells.
+    pub cell_size: f32,
+    /// Distance units keep from obstacles, added around their footprint.
+    pub clearance: f32,
+    /// Units heading to the same cell from which they share a `FlowField` rather than
+    /// search a path each.
+    pub flow_field_agents: usize,
+    /// Paths and flow fields computed per frame, the other units waiting for the next
+    /// frames.
+    pub max_searches_per_frame: usize,
+}
+
+impl Default for PathfindingSettings {
+    fn default() -> Self {
+        Self {
+            cell_size: DEFAULT_CELL_SIZE,
+            clearance: DEFAULT_CLEARANCE,
+            flow_field_agents: DEFAULT_FLOW_FIELD_AGENTS,
+            max_searches_per_frame: DEFAULT_MAX_SEARCHES,
+        }
+    }
+}
+
+/// Where a `Navigation` stands on the way to its destination.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum NavStatus {
+    /// No route, heading straight to the destination if there is one. Without a
+    /// `NavGrid` to plan on, routes stay there.
+    #[default]
+    Idle,
+    /// Waiting for a route, planned in a later frame.
+    Planning,
+    /// Heading to the next waypoint of the route.
+    Moving(Vec3),
+    /// At the end of the route, the destination or the closest point to it.
+    Arrived,
+    Unreachable,
+}
+
+#[derive(Clone, Debug, Default)]
+enum Route {
+    #[default]
+    None,
+    /// Waypoints left, the last one being the end of the route.
+    Path(VecDeque<Vec3>),
+    /// Following the flow field to `goal`, then walking to `end` within it.
+    Flow { goal: IVec2, end: Vec3 },
+}
+
+/// The route of an entity to its destination through the `NavGrid`, planned by the
+/// `PathfindingPlugin` and followed by whatever moves the entity.
+#[derive(Component, Clone, Debug, Default)]
+pub struct Navigation {
+    destination: Option<Vec3>,
+    route: Route,
+    status: NavStatus,
+    /// The cell the route was planned to and the version of the `NavGrid` it was
+    /// planned on.
+    goal: Option<IVec2>,
+    version: u32,
+}
+
+impl Navigation {
+    /// Heads to `destination`. A route is planned again only when the destination
+    /// moves to another cell, so it can follow a moving target.
+    pub fn set_destination(&mut self, destination: Option<Vec3>) {
+        if self.destination == destination {
+            return;
+        }
+        self.destination = destination;
+        if destination.is_none() {
+            *self = Navigation::default();
+        }
+    }
+
+    pub fn destination(&self) -> Option<Vec3> {
+        self.destination
+    }
+
+    pub fn status(&self) -> NavStatus {
+        self.status
+    }
+
+    fn set_route(&mut self, route: Route, goal: IVec2, version: u32) {
+        self.status = match route {
+            Route::None => NavStatus::Unreachable,
+            _ => NavStatus::Idle,
+        };
+        self.route = route;
+        self.goal = Some(goal);
+        self.version = version;
+    }
+
+    fn set_end(&mut self, end: Vec3) {
+        match &mut self.route {
+            Route::Path(waypoints) => {
+                if let Some(last) = waypoints.back_mut() {
+                    *last = end;
+                }
+            }
+            Route::Flow { end: flow_end, .. } => *flow_end = end,
+            Route::None => {}
+        }
+    }
+
+    /// Whether the route still goes through walkable cells only, from `position`.
+    fn is_clear(&self, grid: &NavGrid, position: Vec3) -> bool {
+        match &self.route {
+            Route::Path(waypoints) => std::iter::once(position)
+                .chain(waypoints.iter().copied())
+                .map(|point| grid.cell_at(point))
+                .collect::<Vec<_>>()
+                .windows(2)
+                .all(|segment| grid.line_of_sight(segment[0], segment[1])),
+            // Flow fields are computed again on every new grid
+            Route::Flow { .. } => false,
+            Route::None => self.status != NavStatus::Unreachable,
+        }
+    }
+}
+
+/// The `PathFinder` and the `FlowField`s shared by the units, for the current
+/// version of the `NavGrid`.
+#[derive(Resource, Default)]
+pub struct PathPlanner {
+    finder: PathFinder,
+    flows: HashMap<IVec2, FlowField>,
+}
+
+impl PathPlanner {
+    pub fn flow(&self, goal: IVec2) -> Option<&FlowField> {
+        self.flows.get(&goal)
+    }
+
+    #[cfg(test)]
+    pub fn flow_count(&self) -> usize {
+        self.flows.len()
+    }
+}
+
+/// The `NavGrid` is rebuilt, routes planned and followed in this set. Destinations
+/// are set before it and entities moved after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PathfindingSet;
+
+/// Builds the `NavGrid` from the `NavTerrain` and `Obstacle` footprints, and plans
+/// the routes of the `Navigation` entities through it: A* for the units on their
+/// own, a flow field for the groups heading to the same cell. Routes blocked by a
+/// new obstacle are planned again.
+pub struct PathfindingPlugin;
+
+impl Plugin for PathfindingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PathfindingSettings>()
+            .init_resource::<NavGrid>()
+            .init_resource::<PathPlanner>()
+            .add_systems(Update, (rebuild_nav_grid, plan_routes, follow_routes).chain().in_set(PathfindingSet));
+    }
+}
+
+type FootprintChanged = Or<(Changed<NavTerrain>, Changed<Obstacle>)>;
+
+fn rebuild_nav_grid(
+    settings: Res<PathfindingSettings>,
+    mut grid: ResMut<NavGrid>,
+    terrains: Query<(&Transform, &NavTerrain)>,
+    obstacles: Query<(&Transform, &Obstacle)>,
+    changed: Query<(), FootprintChanged>,
+    mut removed_terrains: RemovedComponents<NavTerrain>,
+    mut removed_obstacles: RemovedComponents<Obstacle>,
+) {
+    let removed = removed_terrains.read().count() + removed_obstacles.read().count() > 0;
+    if changed.is_empty() && !removed && !settings.is_changed() {
+        return;
+    }
+
+    let footprints = terrains.iter().map(|(transform, terrain)| footprint(transform, terrain.size));
+    let Some(bounds) = footprints.reduce(|bounds, footprint| bounds.union(footprint)) else {
+        grid.replace(NavGrid::default());
+        return;
+    };
+
+    let mut rebuilt = NavGrid::covering(bounds, settings.cell_size);
+    for (transform, terrain) in &terrains {
+        rebuilt.fill(footprint(transform, terrain.size), terrain.cost);
+    }
+    for (transform, obstacle) in &obstacles {
+        rebuilt.fill(footprint(transform, obstacle.size).inflate(settings.clearance), BLOCKED);
+    }
+    grid.replace(rebuilt);
+}
+
+fn plan_routes(
+    grid: Res<NavGrid>,
+    settings: Res<PathfindingSettings>,
+    mut planner: ResMut<PathPlanner>,
+    mut agents: Query<(Entity, &Transform, &mut Navigation)>,
+) {
+    // Flow fields are kept while units follow them on the grid they were computed on
+    let followed: HashSet<IVec2> = agents
+        .iter()
+        .filter_map(|(_, _, navigation)| match navigation.route {
+            Route::Flow { goal, .. } => Some(goal),
+            _ => None,
+        })
+        .collect();
+    let planner = &mut *planner;
+    planner.flows.retain(|goal, flow| flow.version() == grid.version() && followed.contains(goal));
+    if grid.is_empty() {
+        return;
+    }
+
+    // The units needing a route, by goal cell in a repeatable order
+    let mut waiting: BTreeMap<(i32, i32), Vec<Entity>> = BTreeMap::new();
+    for (entity, transform, mut navigation) in &mut agents {
+        let Some(destination) = navigation.destination else {
+            continue;
+        };
+        let cell = grid.cell_at(destination);
+        let Some(goal) = grid.nearest_walkable(cell, GOAL_SEARCH_RADIUS) else {
+            navigation.set_route(Route::None, cell, grid.version());
+            continue;
+        };
+
+        let stale = navigation.version != grid.version() && !navigation.is_clear(&grid, transform.translation);
+        if navigation.goal == Some(goal) && !stale {
+            navigation.version = grid.version();
+            navigation.set_end(end(&grid, destination, goal));
+            continue;
+        }
+
+        navigation.route = Route::None;
+        navigation.goal = None;
+        navigation.status = NavStatus::Planning;
+        waiting.entry((goal.x, goal.y)).or_default().push(entity);
+    }
+
+    let mut searches = 0;
+    for ((x, y), entities) in waiting {
+        let goal = IVec2::new(x, y);
+        let shared = entities.len() >= settings.flow_field_agents || planner.flows.contains_key(&goal);
+        if shared {
+            if let Entry::Vacant(entry) = planner.flows.entry(goal) {
+                if searches >= settings.max_searches_per_frame {
+                    continue;
+                }
+                searches += 1;
+                entry.insert(FlowField::new(&grid, goal));
+            }
+
+            for entity in entities {
+                let (_, _, mut navigation) = agents.get_mut(entity).expect("waiting units are agents");
+                let end = end(&grid, navigation.destination.expect("waiting units have a destination"), goal);
+                navigation.set_route(Route::Flow { goal, end }, goal, grid.version());
+            }
+            continue;
+        }
+
+        for entity in entities {
+            if searches >= settings.max_searches_per_frame {
+                break;
+            }
+            searches += 1;
+
+            let (_, transform, mut navigation) = agents.get_mut(entity).expect("waiting units are agents");
+            // Units inside an obstacle first walk out of it
+            let cell = grid.cell_at(transform.translation);
+            let start = grid.nearest_walkable(cell, GOAL_SEARCH_RADIUS).unwrap_or(cell);
+            let route = match planner.finder.find_smooth_path(&grid, start, goal) {
+                Some(cells) => {
+                    let end = end(&grid, navigation.destination.expect("waiting units have a destination"), goal);
+                    let skip = usize::from(start == cell);
+                    let mut waypoints: VecDeque<Vec3> = cells[skip..].iter().map(|&cell| grid.center(cell)).collect();
+                    match waypoints.back_mut() {
+                        Some(last) => *last = end,
+                        None => waypoints.push_back(end),
+                    }
+                    Route::Path(waypoints)
+                }
+                None => Route::None,
+            };
+            navigation.set_route(route, goal, grid.version());
+        }
+    }
+}
+
+/// The point a route to `goal` ends at, `destination` itself if it is walkable.
+fn end(grid: &NavGrid, destination: Vec3, goal: IVec2) -> Vec3 {
+    if grid.cell_at(destination) == goal {
+        destination.with_y(0.0)
+    } else {
+        grid.center(goal)
+    }
+}
+
+fn follow_routes(grid: Res<NavGrid>, planner: Res<PathPlanner>, mut agents: Query<(&Transform, &mut Navigation)>) {
+    let reached = |position: Vec3, point: Vec3| position.with_y(0.0).distance(point.with_y(0.0)) <= WAYPOINT_RADIUS;
+
+    for (transform, mut navigation) in &mut agents {
+        let position = transform.translation;
+        let status = match &mut navigation.route {
+            Route::None => continue,
+            Route::Path(waypoints) => {
+                while waypoints.front().is_some_and(|&waypoint| reached(position, waypoint)) {
+                    waypoints.pop_front();
+                }
+                waypoints.front().map_or(NavStatus::Arrived, |&waypoint| NavStatus::Moving(waypoint))
+            }
+            Route::Flow { goal, end } => {
+                let cell = grid.cell_at(position);
+                if cell == *goal {
+                    if reached(position, *end) {
+                        NavStatus::Arrived
+                    } else {
+                        NavStatus::Moving(*end)
+                    }
+                } else {
+                    // Out of a cell the field does not lead from, such as inside a new
+                    // obstacle, to the closest one it does
+                    let next = planner.flow(*goal).and_then(|flow| {
+                        flow.next_cell(cell).or_else(|| {
+                            grid.nearest_walkable(cell, GOAL_SEARCH_RADIUS).filter(|&out| flow.cost(out).is_some())
+                        })
+                    });
+                    match next {
+                        Some(next) => NavStatus::Moving(grid.center(next)),
+                        None => NavStatus::Unreachable,
+                    }
+                }
+            }
+        };
+        navigation.status = status;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::command::{CommandDispatch, CommandSystem};
+    use crate::core::pathfinding::synthetic;
+    use crate::core::selection::{SelectMode, Selectable, Selection, SelectionPlugin};
+    use crate::core::testing::{Rng, TestApp};
+    use crate::core::unit::{Order, Unit, UnitCommand, UnitPlugin};
+    use bevy::prelude::GlobalTransform;
+    use std::time::{Duration, Instant};
+
+    fn app() -> TestApp {
+        let mut app = TestApp::new((SelectionPlugin, UnitPlugin, PathfindingPlugin));
+        app.world_mut().spawn((NavTerrain::new(Vec2::splat(10.0)), Transform::default()));
+        app
+    }
+
+    fn spawn_unit(app: &mut TestApp, position: Vec3) -> Entity {
+        let transform = Transform::from_translation(position);
+        let unit = app
+            .world_mut()
+            .spawn((Unit::default(), Selectable::default(), transform, GlobalTransform::from(transform)))
+            .id();
+        app.world_mut().resource_mut::<Selection>().select(SelectMode::Add, [unit]);
+        unit
+    }
+
+    fn spawn_obstacle(app: &mut TestApp, position: Vec3, size: Vec2) -> Rect {
+        let transform = Transform::from_translation(position);
+        app.world_mut().spawn((Obstacle::new(size), transform));
+        footprint(&transform, size)
+    }
+
+    fn position(app: &TestApp, entity: Entity) -> Vec3 {
+        app.world().get::<Transform>(entity).unwrap().translation
+    }
+
+    /// Runs frames until the unit is idle, checking it never steps on `footprint`.
+    fn walk(app: &mut TestApp, unit: Entity, footprint: Rect, frames: usize) -> usize {
+        for frame in 0..frames {
+            app.update();
+            let position = position(app, unit);
+            assert!(
+                !footprint.contains(Vec2::new(position.x, position.z)),
+                "stepped on {} at frame {}",
+                position,
+                frame
+            );
+            if app.world().get::<Order>(unit) == Some(&Order::Idle) {
+                return frame;
+            }
+        }
+        panic!("still walking after {} frames", frames);
+    }
+
+    #[test]
+    fn units_walk_around_obstacles() {
+        let mut app = app();
+        let wall = spawn_obstacle(&mut app, Vec3::ZERO, Vec2::new(1.0, 6.0));
+        let unit = spawn_unit(&mut app, Vec3::new(-3.0, 0.5, 0.0));
+        app.world_mut()
+            .resource_mut::<CommandSystem>()
+            .push_command(UnitCommand::Move { target: Vec3::new(3.0, 0.0, 0.0).into() });
+
+        let frames = walk(&mut app, unit, wall, 600);
+        assert!(position(&app, unit).distance(Vec3::new(3.0, 0.5, 0.0)) < 1e-3);
+        // Around the wall takes longer than the 2 seconds of the straight line
+        assert!(frames > 150, "arrived after {} frames", frames);
+    }
+
+    #[test]
+    fn placing_a_building_plans_the_routes_again() {
+        let mut app = app();
+        let unit = spawn_unit(&mut app, Vec3::new(-4.0, 0.5, 1.0));
+        app.world_mut()
+            .resource_mut::<CommandSystem>()
+            .push_command(UnitCommand::Move { target: Vec3::new(4.0, 0.0, 1.0).into() });
+        app.run(10);
+        assert!(matches!(app.world().get::<Navigation>(unit).unwrap().status(), NavStatus::Moving(_)));
+
+        let building = spawn_obstacle(&mut app, Vec3::new(0.0, 0.0, 1.0), Vec2::new(2.0, 4.0));
+        walk(&mut app, unit, building, 600);
+        assert!(position(&app, unit).distance(Vec3::new(4.0, 0.5, 1.0)) < 1e-3);
+
+        // A destination inside a building ends next to it
+        app.world_mut()
+            .resource_mut::<CommandSystem>()
+            .push_command(UnitCommand::Move { target: Vec3::new(0.0, 0.0, 1.0).into() });
+        walk(&mut app, unit, building, 600);
+        assert!(position(&app, unit).distance(Vec3::new(0.0, 0.5, 1.0)) < 2.0);
+    }
+
+    fn spawn_agents(app: &mut TestApp, count: usize, destination: impl Fn(usize) -> Vec3) -> Vec<Entity> {
+        let mut rng = Rng(3);
+        (0..count)
+            .map(|index| {
+                let position = Vec3::new(rng.next_f32() * 60.0 - 30.0, 0.0, rng.next_f32() * 60.0 - 30.0);
+                let mut navigation = Navigation::default();
+                navigation.set_destination(Some(destination(index)));
+                app.world_mut().spawn((navigation, Transform::from_translation(position))).id()
+            })
+            .collect()
+    }
+
+    fn statuses(app: &TestApp, agents: &[Entity]) -> (usize, usize) {
+        agents.iter().fold((0, 0), |(moving, planning), &agent| {
+            match app.world().get::<Navigation>(agent).unwrap().status() {
+                NavStatus::Moving(_) => (moving + 1, planning),
+                NavStatus::Planning => (moving, planning + 1),
+                status => panic!("unexpected {:?}", status),
+            }
+        })
+    }
+
+    #[test]
+    fn groups_share_a_flow_field() {
+        let mut app = TestApp::new(PathfindingPlugin);
+        app.world_mut().spawn((NavTerrain::new(Vec2::splat(64.0)), Transform::default()));
+        app.world_mut().spawn((Obstacle::new(Vec2::new(2.0, 40.0)), Transform::default()));
+
+        let group = spawn_agents(&mut app, 400, |_| Vec3::new(20.0, 0.0, 5.0));
+        app.update();
+        assert_eq!(statuses(&app, &group), (400, 0));
+        assert_eq!(app.world().resource::<PathPlanner>().flow_count(), 1);
+
+        // On their own, units search a path each, a few per frame
+        let loners = spawn_agents(&mut app, 80, |index| Vec3::new(index as f32 * 0.5 - 20.0, 0.0, -25.0));
+        app.update();
+        assert_eq!(statuses(&app, &loners), (DEFAULT_MAX_SEARCHES, 80 - DEFAULT_MAX_SEARCHES));
+        app.run(2);
+        assert_eq!(statuses(&app, &loners), (80, 0));
+    }
+
+    #[test]
+    fn hundreds_of_units_keep_to_the_search_budget() {
+        let mut app = TestApp::new(PathfindingPlugin);
+        app.world_mut().spawn((NavTerrain::new(Vec2::splat(64.0)), Transform::default()));
+        app.world_mut().spawn((Obstacle::new(Vec2::new(2.0, 40.0)), Transform::default()));
+        app.world_mut().spawn((Obstacle::new(Vec2::new(30.0, 2.0)), Transform::from_xyz(-10.0, 0.0, 15.0)));
+
+        // Two large groups, and one unit in ten on its own
+        let agents = spawn_agents(&mut app, 600, |index| match index % 10 {
+            0..=5 => Vec3::new(20.0, 0.0, 20.0),
+            6..=8 => Vec3::new(-20.0, 0.0, 10.0),
+            _ => Vec3::new(index as f32 * 0.1 - 30.0, 0.0, -25.0),
+        });
+        let loners: Vec<Entity> = agents.iter().copied().skip(9).step_by(10).collect();
+
+        // A search per unit on its own and per flow field, no more in a frame than allowed
+        app.update();
+        let (searched, _) = statuses(&app, &loners);
+        let flows = app.world().resource::<PathPlanner>().flow_count();
+        assert_eq!(searched + flows, DEFAULT_MAX_SEARCHES);
+
+        app.update();
+        assert_eq!(statuses(&app, &agents), (600, 0));
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_searches_on_random_maps() {
+        for (size, density) in [(64, 0.2), (128, 0.25), (256, 0.3)] {
+            let grid = synthetic::random(size, density, 11);
+            let mut finder = PathFinder::default();
+            let mut rng = Rng(5);
+            let mut cell = || synthetic::walkable_cell(&mut rng, &grid);
+            let queries: Vec<(IVec2, IVec2)> = (0..500).map(|_| (cell(), cell())).collect();
+
+            let start = Instant::now();
+            let found =
+                queries.iter().filter(|&&(from, to)| finder.find_smooth_path(&grid, from, to).is_some()).count();
+            let searches = start.elapsed();
+
+            let start = Instant::now();
+            let fields: Vec<FlowField> = queries[..20].iter().map(|&(_, to)| FlowField::new(&grid, to)).collect();
+            let flows = start.elapsed();
+
+            println!(
+                "{size}x{size} at {density}: {found}/500 paths in {searches:?}, {:?} per search; {} flow fields in {flows:?}",
+                searches / 500,
+                fields.len(),
+            );
+        }
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_hundreds_of_units() {
+        let mut app = TestApp::new((SelectionPlugin, UnitPlugin, PathfindingPlugin));
+        app.world_mut().spawn((NavTerrain::new(Vec2::splat(128.0)), Transform::default()));
+        let mut rng = Rng(9);
+        for _ in 0..200 {
+            let position = Vec3::new(rng.next_f32() * 120.0 - 60.0, 0.0, rng.next_f32() * 120.0 - 60.0);
+            app.world_mut()
+                .spawn((Obstacle::new(Vec2::splat(1.0 + rng.next_f32() * 3.0)), Transform::from_translation(position)));
+        }
+        for index in 0..600 {
+            let position = Vec3::new(rng.next_f32() * 120.0 - 60.0, 0.5, rng.next_f32() * 120.0 - 60.0);
+            let unit = spawn_unit(&mut app, position);
+            // A few large groups and units on their own
+            let target = match index % 10 {
+                0..=5 => Vec3::new(50.0, 0.0, 50.0),
+                6..=8 => Vec3::new(-50.0, 0.0, 10.0),
+                _ => Vec3::new(rng.next_f32() * 120.0 - 60.0, 0.0, rng.next_f32() * 120.0 - 60.0),
+            };
+            app.world_mut().resource_mut::<Selection>().select(SelectMode::Replace, [unit]);
+            app.world_mut().resource_mut::<CommandSystem>().push_command(UnitCommand::Move { target: target.into() });
+            app.update();
+        }
+
+        let start = Instant::now();
+        app.run(300);
+        let frame = start.elapsed() / 300;
+        println!("600 units: {:?} per frame", frame);
+        assert!(frame < Duration::from_millis(16), "{:?} per frame", frame);
+    }
+}
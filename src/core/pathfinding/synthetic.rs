@@ -0,0 +1,47 @@
+//! Synthetic maps for the pathfinding tests and benchmarks, with cells one unit wide
+//! and the corner of cell `(0, 0)` at the world origin.
+
+use crate::core::pathfinding::grid::{NavGrid, BLOCKED, DEFAULT_COST};
+use crate::core::testing::Rng;
+use bevy::math::{IVec2, Rect, Vec2};
+
+/// A `size` by `size` map of plain ground.
+pub fn open(size: i32) -> NavGrid {
+    let mut grid = NavGrid::new(Vec2::ZERO, 1.0, IVec2::splat(size));
+    grid.fill(Rect::new(0.0, 0.0, size as f32, size as f32), DEFAULT_COST);
+    grid
+}
+
+/// Walls across the map every fourth column, open at the top and the bottom in
+/// turn, so crossing the map winds through all of them.
+pub fn walls(size: i32) -> NavGrid {
+    let mut grid = open(size);
+    for (wall, x) in (3..size - 1).step_by(4).enumerate() {
+        let gap = if wall % 2 == 0 { size - 1 } else { 0 };
+        (0..size).filter(|&y| y != gap).for_each(|y| grid.set_cost(IVec2::new(x, y), BLOCKED));
+    }
+    grid
+}
+
+/// Blocks cells at random, `density` being the share of them blocked.
+pub fn random(size: i32, density: f32, seed: u64) -> NavGrid {
+    let mut grid = open(size);
+    let mut rng = Rng(seed);
+    for index in 0..(size * size) as usize {
+        if rng.next_f32() < density {
+            grid.set_cost(grid.cell(index), BLOCKED);
+        }
+    }
+    grid
+}
+
+/// A walkable cell of `grid`, picked with `rng`.
+pub fn walkable_cell(rng: &mut Rng, grid: &NavGrid) -> IVec2 {
+    let cells = (grid.size().x * grid.size().y) as u64;
+    loop {
+        let cell = grid.cell((rng.next_u64() % cells) as usize);
+        if grid.is_walkable(cell) {
+            return cell;
+        }
+    }
+}
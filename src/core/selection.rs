@@ -0,0 +1,426 @@
+use crate::core::command::set::CommandSet;
+use crate::core::command::{
+    downcast_commands, Command, CommandAppExt, CommandDispatch, CommandHandler, CommandDispatchSet, CommandSystem,
+    ExtCommandHandler,
+};
+use crate::core::input::{InputPlugin, InputSet};
+use crate::core::SerializeEnum;
+use bevy::math::{Ray3d, Rect, Vec2, Vec3};
+use bevy::prelude::{
+    App, Camera, Camera3d, Component, Entity, GlobalTransform, IntoScheduleConfigs, Local, Plugin, PreUpdate, Query,
+    RemovedComponents, ResMut, Resource, Update, Window, With,
+};
+use bevy::window::PrimaryWindow;
+
+/// Selecting with the mouse. `Click`, `Add` and `Toggle` are held with the button
+/// that selects, the modifiers of their bindings telling how the picked entities
+/// change the `Selection`. `Box` is active on the frames the mouse is dragged with
+/// that button held, turning the click into a box.
+///
+/// Every command carries the cursor position in window coordinates, filled in right
+/// after the bindings fire, so a recorded gesture replays where it was made.
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, PartialEq, SerializeEnum, Command)]
+#[command(namespace = "selection", id = 3)]
+pub enum SelectionCommand {
+    Click(Vec2)  = 0x1 << 0,
+    Box(Vec2)    = 0x1 << 1,
+    Add(Vec2)    = 0x1 << 2,
+    Toggle(Vec2) = 0x1 << 3,
+}
+
+impl SelectionCommand {
+    /// The cursor position the command was given at.
+    pub fn position(&self) -> Vec2 {
+        match self {
+            SelectionCommand::Click(position)
+            | SelectionCommand::Box(position)
+            | SelectionCommand::Add(position)
+            | SelectionCommand::Toggle(position) => *position,
+        }
+    }
+
+    /// The same command, given at `position`.
+    pub fn at(self, position: Vec2) -> Self {
+        match self {
+            SelectionCommand::Click(_)  => SelectionCommand::Click(position),
+            SelectionCommand::Box(_)    => SelectionCommand::Box(position),
+            SelectionCommand::Add(_)    => SelectionCommand::Add(position),
+            SelectionCommand::Toggle(_) => SelectionCommand::Toggle(position),
+        }
+    }
+}
+
+/// Distance the cursor has to travel, in pixels, before a click becomes a box.
+const DRAG_THRESHOLD: f32 = 4.0;
+const DEFAULT_SELECTABLE_RADIUS: f32 = 0.5;
+
+/// An entity the player can select, picked by clicks within `radius` of its origin.
+#[derive(Component, Clone, Copy)]
+pub struct Selectable {
+    pub radius: f32,
+}
+
+impl Selectable {
+    pub fn new(radius: f32) -> Self {
+        Self { radius }
+    }
+}
+
+impl Default for Selectable {
+    fn default() -> Self {
+        Self::new(DEFAULT_SELECTABLE_RADIUS)
+    }
+}
+
+/// How the entities picked by a click or a box change the `Selection`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectMode {
+    Replace,
+    Add,
+    Toggle,
+}
+
+/// The selected entities, in the order they were selected.
+#[derive(Resource, Default)]
+pub struct Selection {
+    entities: Vec<Entity>,
+}
+
+impl Selection {
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(&entity)
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        self.entities.retain(|&selected| selected != entity);
+    }
+
+    /// Applies a click or a box that picked `entities`. Replacing with nothing
+    /// deselects everything, as clicking the ground does.
+    pub fn select(&mut self, mode: SelectMode, entities: impl IntoIterator<Item = Entity>) {
+        if mode == SelectMode::Replace {
+            self.entities.clear();
+        }
+
+        for entity in entities {
+            match (mode, self.contains(entity)) {
+                (SelectMode::Toggle, true) => self.remove(entity),
+                (_, true)                  => {}
+                (_, false)                 => self.entities.push(entity),
+            }
+        }
+    }
+}
+
+/// What the player asked for when releasing the selecting button.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelectionRequest {
+    /// Pick the entity under a cursor position.
+    Click { position: Vec2, mode: SelectMode },
+    /// Pick every entity inside a screen rectangle.
+    Box { rect: Rect, mode: SelectMode },
+}
+
+impl SelectionRequest {
+    pub fn mode(&self) -> SelectMode {
+        match self {
+            SelectionRequest::Click { mode, .. } => *mode,
+            SelectionRequest::Box { mode, .. }   => *mode,
+        }
+    }
+}
+
+struct Gesture {
+    start: Vec2,
+    end: Vec2,
+    mode: SelectMode,
+    boxing: bool,
+}
+
+/// Turns the `SelectionCommand`s of every frame into clicks and boxes.
+///
+/// A gesture starts on the first frame a selecting command is held and ends on the
+/// frame none is, taking the mode of the last frame so the modifiers can be pressed
+/// while dragging. It runs from the positions carried by the commands, never from the
+/// live cursor.
+#[derive(Resource, Default)]
+pub struct SelectionSystem {
+    held: Option<SelectMode>,
+    dragged: bool,
+    cursor: Option<Vec2>,
+    gesture: Option<Gesture>,
+}
+
+impl SelectionSystem {
+    /// Advances the gesture with the commands of this frame, returning the request
+    /// made when it ends.
+    pub fn update(&mut self) -> Option<SelectionRequest> {
+        match (self.held, &mut self.gesture) {
+            (Some(mode), None) => {
+                self.gesture = self.cursor.map(|start| Gesture { start, end: start, mode, boxing: false });
+                None
+            }
+            (Some(mode), Some(gesture)) => {
+                gesture.mode = mode;
+                gesture.end = self.cursor.unwrap_or(gesture.end);
+                gesture.boxing |= self.dragged && gesture.start.distance(gesture.end) >= DRAG_THRESHOLD;
+                None
+            }
+            (None, Some(_)) => {
+                let Gesture { start, end, mode, boxing } = self.gesture.take()?;
+                Some(match boxing {
+                    true  => SelectionRequest::Box { rect: Rect::from_corners(start, end), mode },
+                    false => SelectionRequest::Click { position: start, mode },
+                })
+            }
+            (None, None) => None,
+        }
+    }
+
+    /// The box being dragged, in window coordinates, for drawing it.
+    pub fn drag_rect(&self) -> Option<Rect> {
+        self.gesture
+            .as_ref()
+            .filter(|gesture| gesture.boxing)
+            .map(|gesture| Rect::from_corners(gesture.start, gesture.end))
+    }
+}
+
+impl CommandHandler<SelectionCommand> for SelectionSystem {
+    fn handle_commands(&mut self, commands: &CommandSet<SelectionCommand>) {
+        self.held = if commands.contains(&SelectionCommand::Toggle(Vec2::ZERO)) {
+            Some(SelectMode::Toggle)
+        } else if commands.contains(&SelectionCommand::Add(Vec2::ZERO)) {
+            Some(SelectMode::Add)
+        } else if commands.contains(&SelectionCommand::Click(Vec2::ZERO)) {
+            Some(SelectMode::Replace)
+        } else {
+            None
+        };
+        self.dragged = commands.contains(&SelectionCommand::Box(Vec2::ZERO));
+        self.cursor = commands.iter().next().map(SelectionCommand::position);
+    }
+}
+
+impl ExtCommandHandler for SelectionSystem {
+    fn handle_ext_commands(&mut self, commands: &[&dyn Command]) {
+        self.handle_commands(&downcast_commands::<SelectionCommand>(commands));
+    }
+}
+
+/// The entity whose sphere `ray` enters first, if any.
+pub fn pick(ray: Ray3d, candidates: impl IntoIterator<Item = (Entity, Vec3, f32)>) -> Option<Entity> {
+    candidates
+        .into_iter()
+        .filter_map(|(entity, center, radius)| {
+            let to_center = center - ray.origin;
+            let along = to_center.dot(*ray.direction);
+            let squared_miss = to_center.length_squared() - along * along;
+            let depth = radius * radius - squared_miss;
+            (depth >= 0.0 && along + depth.sqrt() >= 0.0).then(|| (entity, along - depth.sqrt()))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+/// Selects `Selectable` entities from the `SelectionCommand` bindings, by ray casts
+/// from the `Camera3d` for clicks and by their projected positions for boxes.
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(InputPlugin::<SelectionCommand>::default())
+            .register_command::<SelectionCommand>()
+            .init_resource::<Selection>()
+            .init_resource::<SelectionSystem>()
+            .add_command_handler::<SelectionSystem>("selection.*")
+            .add_systems(PreUpdate, locate_selection_commands.after(InputSet))
+            .add_systems(Update, (prune_selection, update_selection).chain().after(CommandDispatchSet));
+    }
+}
+
+/// Gives the selection commands fired by the bindings the cursor position, the last
+/// one over the window while it is outside. They are dropped until the cursor has
+/// been over the window.
+fn locate_selection_commands(
+    mut queue: ResMut<CommandSystem>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut last_cursor: Local<Option<Vec2>>,
+) {
+    if let Some(cursor) = windows.single().ok().and_then(Window::cursor_position) {
+        *last_cursor = Some(cursor);
+    }
+
+    let commands = queue.take_commands::<SelectionCommand>();
+    if let Some(cursor) = *last_cursor {
+        commands.into_iter().for_each(|command| queue.push_command(command.at(cursor)));
+    }
+}
+
+fn update_selection(
+    mut system: ResMut<SelectionSystem>,
+    mut selection: ResMut<Selection>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    selectables: Query<(Entity, &GlobalTransform, &Selectable)>,
+) {
+    let Some(request) = system.update() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+
+    let picked: Vec<Entity> = match request {
+        SelectionRequest::Click { position, .. } => camera
+            .viewport_to_world(camera_transform, position)
+            .ok()
+            .and_then(|ray| {
+                pick(ray, selectables.iter().map(|(entity, transform, selectable)| {
+                    (entity, transform.translation(), selectable.radius)
+                }))
+            })
+            .into_iter()
+            .collect(),
+        SelectionRequest::Box { rect, .. } => selectables
+            .iter()
+            .filter(|(_, transform, _)| {
+                camera
+                    .world_to_viewport(camera_transform, transform.translation())
+                    .is_ok_and(|position| rect.contains(position))
+            })
+            .map(|(entity, ..)| entity)
+            .collect(),
+    };
+    selection.select(request.mode(), picked);
+}
+
+/// Forgets the entities that were despawned or stopped being selectable.
+fn prune_selection(mut removed: RemovedComponents<Selectable>, mut selection: ResMut<Selection>) {
+    for entity in removed.read() {
+        selection.remove(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::command::replay::CommandRecorder;
+    use crate::core::testing::TestApp;
+    use bevy::math::Dir3;
+    use bevy::prelude::{KeyCode, MouseButton};
+
+    fn entities(count: u32) -> Vec<Entity> {
+        (1..=count).map(Entity::from_raw).collect()
+    }
+
+    #[test]
+    fn modifiers_pick_the_mode() {
+        let mut app = TestApp::new(SelectionPlugin);
+        app.spawn_window(800.0, 600.0);
+        app.set_cursor(Some(Vec2::new(100.0, 50.0)));
+        app.press_mouse(MouseButton::Left);
+        app.update();
+        assert_eq!(app.last_commands(), ["selection.click(100, 50)"]);
+
+        app.press(KeyCode::ShiftLeft);
+        app.update();
+        assert_eq!(app.last_commands(), ["selection.add(100, 50)"]);
+
+        // Out of the window, commands keep the last position over it
+        app.release(KeyCode::ShiftLeft);
+        app.press(KeyCode::ControlRight);
+        app.set_cursor(None);
+        app.move_mouse(Vec2::new(10.0, 0.0));
+        app.update();
+        assert_eq!(app.last_commands(), ["selection.box(100, 50)", "selection.toggle(100, 50)"]);
+    }
+
+    #[test]
+    fn gestures_end_as_clicks_or_boxes() {
+        let mut system = SelectionSystem::default();
+        let held = |system: &mut SelectionSystem, commands: &[SelectionCommand]| {
+            system.handle_commands(&commands.iter().copied().collect());
+        };
+        let (start, end) = (Vec2::new(100.0, 100.0), Vec2::new(102.0, 101.0));
+
+        held(&mut system, &[SelectionCommand::Click(start)]);
+        assert_eq!(system.update(), None);
+        held(&mut system, &[SelectionCommand::Click(end), SelectionCommand::Box(end)]);
+        assert_eq!(system.update(), None);
+        assert_eq!(system.drag_rect(), None);
+        held(&mut system, &[]);
+        assert_eq!(system.update(), Some(SelectionRequest::Click { position: start, mode: SelectMode::Replace }));
+
+        let end = Vec2::new(50.0, 150.0);
+        held(&mut system, &[SelectionCommand::Click(start)]);
+        system.update();
+        held(&mut system, &[SelectionCommand::Add(end), SelectionCommand::Box(end)]);
+        system.update();
+        let rect = Rect::new(50.0, 100.0, 100.0, 150.0);
+        assert_eq!(system.drag_rect(), Some(rect));
+        held(&mut system, &[]);
+        assert_eq!(system.update(), Some(SelectionRequest::Box { rect, mode: SelectMode::Add }));
+    }
+
+    #[test]
+    fn recorded_gestures_replay_where_they_were_made() {
+        let mut app = TestApp::new(SelectionPlugin);
+        app.spawn_window(800.0, 600.0);
+        app.world_mut().resource_mut::<CommandRecorder>().record();
+        app.set_cursor(Some(Vec2::new(300.0, 200.0)));
+        app.press_mouse(MouseButton::Left);
+        app.update();
+        app.release_mouse(MouseButton::Left);
+        app.update();
+        let recording = app.world_mut().resource_mut::<CommandRecorder>().stop_recording().unwrap();
+
+        app.set_cursor(Some(Vec2::new(10.0, 10.0)));
+        app.world_mut().resource_mut::<CommandRecorder>().play(recording);
+        while app.world().resource::<CommandRecorder>().is_playing() {
+            app.update();
+        }
+        let clicks: Vec<&String> =
+            app.all_commands().iter().flatten().filter(|command| command.starts_with("selection")).collect();
+        assert_eq!(clicks, ["selection.click(300, 200)", "selection.click(300, 200)"]);
+    }
+
+    #[test]
+    fn modes_change_the_selection() {
+        let [a, b, c] = entities(3)[..] else { unreachable!() };
+        let mut selection = Selection::default();
+
+        selection.select(SelectMode::Replace, [a, b]);
+        selection.select(SelectMode::Add, [b, c]);
+        assert_eq!(selection.entities(), [a, b, c]);
+
+        selection.select(SelectMode::Toggle, [a]);
+        assert_eq!(selection.entities(), [b, c]);
+        selection.select(SelectMode::Toggle, [a]);
+        assert_eq!(selection.entities(), [b, c, a]);
+
+        selection.select(SelectMode::Replace, []);
+        assert!(selection.entities().is_empty());
+    }
+
+    #[test]
+    fn rays_pick_the_nearest_selectable() {
+        let [near, far, beside] = entities(3)[..] else { unreachable!() };
+        let ray = Ray3d::new(Vec3::new(0.0, 10.0, 0.0), Dir3::NEG_Y);
+        let candidates = [
+            (far, Vec3::ZERO, 1.0),
+            (near, Vec3::new(0.4, 5.0, 0.0), 0.5),
+            (beside, Vec3::new(2.0, 8.0, 0.0), 1.0),
+        ];
+
+        assert_eq!(pick(ray, candidates), Some(near));
+        assert_eq!(pick(ray, candidates[..1].iter().copied()), Some(far));
+        assert_eq!(pick(ray, candidates[2..].iter().copied()), None);
+        // Behind the ray's origin
+        assert_eq!(pick(ray, [(near, Vec3::new(0.0, 12.0, 0.0), 1.0)]), None);
+    }
+}
@@ -0,0 +1,191 @@
+//! Headless harness driving the input → command → subsystem pipeline in tests.
+//!
+//! `TestApp` builds an `App` from `MinimalPlugins` and Bevy's input plugin, without
+//! any window, renderer or GPU. Tests feed it synthetic input events, advance time by
+//! a fixed step per frame, then check the resulting transforms and the commands that
+//! went through the `CommandSystem`.
+
+use crate::core::command::{Command, CommandAppExt, ExtCommandHandler};
+use crate::core::input::{InputParams, InputState};
+use bevy::ecs::system::SystemState;
+use bevy::input::keyboard::{Key, KeyboardInput, NativeKey};
+use bevy::input::mouse::{MouseButtonInput, MouseMotion, MouseScrollUnit, MouseWheel};
+use bevy::input::ButtonState;
+use bevy::math::{Vec2, Vec3};
+use bevy::app::Plugins;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::{PrimaryWindow, WindowResolution};
+use std::time::Duration;
+
+/// Length of a frame, 60 frames per second.
+pub const FRAME_STEP: Duration = Duration::from_nanos(16_666_667);
+
+/// Every command dispatched through the `CommandSystem`, written as strings, one
+/// list per frame.
+#[derive(Resource, Default)]
+pub struct CommandLog {
+    frames: Vec<Vec<String>>,
+}
+
+impl ExtCommandHandler for CommandLog {
+    fn handle_ext_commands(&mut self, commands: &[&dyn Command]) {
+        self.frames.push(commands.iter().map(|command| command.to_string()).collect());
+    }
+}
+
+pub struct TestApp {
+    app: App,
+    window: Entity,
+}
+
+impl TestApp {
+    /// A headless app advancing `FRAME_STEP` per frame, with `plugins` added.
+    pub fn new<M>(plugins: impl Plugins<M>) -> Self {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::input::InputPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME_STEP))
+            .add_plugins(plugins)
+            .init_resource::<CommandLog>()
+            .add_command_handler::<CommandLog>("*");
+
+        // The first update only starts the clock, so tests begin with a full step
+        app.update();
+        Self { app, window: Entity::PLACEHOLDER }
+    }
+
+    pub fn app(&mut self) -> &mut App {
+        &mut self.app
+    }
+
+    pub fn world(&self) -> &World {
+        self.app.world()
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    pub fn update(&mut self) {
+        self.app.update();
+    }
+
+    /// Runs `frames` frames.
+    pub fn run(&mut self, frames: usize) {
+        (0..frames).for_each(|_| self.app.update());
+    }
+
+    /// Spawns a `Camera3d` at `translation`, looking at the origin.
+    pub fn spawn_camera(&mut self, translation: Vec3) -> Entity {
+        self.world_mut()
+            .spawn((Camera3d::default(), Transform::from_translation(translation).looking_at(Vec3::ZERO, Vec3::Y)))
+            .id()
+    }
+
+    pub fn camera_transform(&mut self) -> Transform {
+        let mut query = self.world_mut().query_filtered::<&Transform, With<Camera3d>>();
+        *query.single(self.world()).expect("the test app has one camera")
+    }
+
+    /// Spawns the primary window, which headless apps lack.
+    pub fn spawn_window(&mut self, width: f32, height: f32) -> Entity {
+        let window = Window { resolution: WindowResolution::new(width, height), ..default() };
+        self.window = self.world_mut().spawn((window, PrimaryWindow)).id();
+        self.window
+    }
+
+    pub fn window_mut(&mut self) -> Mut<'_, Window> {
+        let window = self.window;
+        self.world_mut().get_mut::<Window>(window).expect("the test app has a window")
+    }
+
+    /// Moves the cursor over the primary window, or out of it with `None`.
+    pub fn set_cursor(&mut self, position: Option<Vec2>) {
+        self.window_mut().set_cursor_position(position);
+    }
+
+    pub fn press(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Pressed);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Released);
+    }
+
+    fn send_key(&mut self, key_code: KeyCode, state: ButtonState) {
+        let window = self.window;
+        self.world_mut().send_event(KeyboardInput {
+            key_code,
+            logical_key: Key::Unidentified(NativeKey::Unidentified),
+            state,
+            text: None,
+            repeat: false,
+            window,
+        });
+    }
+
+    pub fn press_mouse(&mut self, button: MouseButton) {
+        self.send_mouse(button, ButtonState::Pressed);
+    }
+
+    pub fn release_mouse(&mut self, button: MouseButton) {
+        self.send_mouse(button, ButtonState::Released);
+    }
+
+    fn send_mouse(&mut self, button: MouseButton, state: ButtonState) {
+        let window = self.window;
+        self.world_mut().send_event(MouseButtonInput { button, state, window });
+    }
+
+    /// Moves the mouse by `delta` pixels during the next frame.
+    pub fn move_mouse(&mut self, delta: Vec2) {
+        self.world_mut().send_event(MouseMotion { delta });
+    }
+
+    /// Turns the wheel by `lines` during the next frame.
+    pub fn scroll(&mut self, lines: f32) {
+        let window = self.window;
+        self.world_mut().send_event(MouseWheel { unit: MouseScrollUnit::Line, x: 0.0, y: lines, window });
+    }
+
+    /// Runs `f` on the input state as the binding systems saw it during the last frame.
+    pub fn with_input_state<R>(&mut self, f: impl FnOnce(&InputState) -> R) -> R {
+        let mut state = SystemState::<InputParams>::new(self.world_mut());
+        let params = state.get(self.world());
+        f(&params.state())
+    }
+
+    /// Commands dispatched during the last frame.
+    pub fn last_commands(&self) -> &[String] {
+        self.world().resource::<CommandLog>().frames.last().map_or(&[], Vec::as_slice)
+    }
+
+    /// Commands dispatched during every frame so far.
+    pub fn all_commands(&self) -> &[Vec<String>] {
+        &self.world().resource::<CommandLog>().frames
+    }
+}
+
+/// Deterministic xorshift generator, so randomized tests and benchmarks reproduce.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next_u64(&mut self) -> u64 {
+        // Zero is the one state xorshift never leaves
+        self.0 = self.0.max(1);
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Below `n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Between 0 and 1.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
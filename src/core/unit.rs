@@ -0,0 +1,650 @@
+use crate::core::command::set::CommandSet;
+use crate::core::command::wire::{WireError, WireReader, WireWriter};
+use crate::core::command::{
+    downcast_commands, Command, CommandAppExt, CommandDispatch, CommandHandler, CommandDispatchSet, CommandPayload,
+    CommandSystem, ExtCommandHandler,
+};
+use crate::core::input::{InputPlugin, InputSet};
+use crate::core::order::{
+    OrderCompleted, OrderQueue, OrderQueuePlugin, OrderQueueSet, OrderStarted, QueueMode, Queued,
+};
+use crate::core::pathfinding::{NavStatus, Navigation, PathfindingSet};
+use crate::core::selection::{pick, Selectable, Selection};
+use crate::core::SerializeEnum;
+use bevy::gizmos::config::GizmoConfigStore;
+use bevy::math::primitives::InfinitePlane3d;
+use bevy::math::{Ray3d, Vec3};
+use bevy::prelude::{
+    resource_exists, App, Camera, Camera3d, Color, Component, Entity, EventReader, EventWriter, Gizmos,
+    GlobalTransform, IntoScheduleConfigs, Plugin, PreUpdate, Query, Res, ResMut, Resource, Time, Transform, Update,
+    Window, With,
+};
+use bevy::window::PrimaryWindow;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// Orders given to the selected units, each with the `QueueMode` telling whether it
+/// replaces their orders or is appended to them.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq, SerializeEnum, Command)]
+#[command(namespace = "unit", id = 5)]
+pub enum UnitCommand {
+    Move { target: Queued<Vec3> }           = 0x1 << 0,
+    /// Moves like `Move`, engaging the enemies met on the way once units can fight.
+    AttackMove { target: Queued<Vec3> }     = 0x1 << 1,
+    Attack { entity: Queued<Entity> }       = 0x1 << 2,
+    Stop { mode: QueueMode }                = 0x1 << 3,
+    HoldPosition { mode: QueueMode }        = 0x1 << 4,
+    /// Goes back and forth from the unit's position through the waypoints.
+    Patrol { waypoints: Queued<Vec<Vec3>> } = 0x1 << 5,
+}
+
+impl UnitCommand {
+    pub fn mode(&self) -> QueueMode {
+        match self {
+            UnitCommand::Move { target } | UnitCommand::AttackMove { target } => target.mode,
+            UnitCommand::Attack { entity }                                   => entity.mode,
+            UnitCommand::Stop { mode } | UnitCommand::HoldPosition { mode }  => *mode,
+            UnitCommand::Patrol { waypoints }                                => waypoints.mode,
+        }
+    }
+}
+
+/// Orders given with the mouse to whatever is under the cursor, standing for a
+/// `UnitCommand`: `Smart` attacks the unselected `Selectable` under the cursor or
+/// moves to the ground there, `AttackMove` and `Patrol` target the ground.
+///
+/// Bindings fire them aimed at the `Cursor`, and they are aimed at what is under it
+/// right after, so the dispatched and recorded commands carry their target.
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, PartialEq, SerializeEnum, Command)]
+#[command(namespace = "order", id = 6)]
+pub enum OrderCommand {
+    Smart(Queued<OrderTarget>)      = 0x1 << 0,
+    AttackMove(Queued<OrderTarget>) = 0x1 << 1,
+    Patrol(Queued<OrderTarget>)     = 0x1 << 2,
+}
+
+impl OrderCommand {
+    fn payload(&self) -> Queued<OrderTarget> {
+        match self {
+            OrderCommand::Smart(payload) | OrderCommand::AttackMove(payload) | OrderCommand::Patrol(payload) => {
+                *payload
+            }
+        }
+    }
+
+    pub fn target(&self) -> OrderTarget {
+        self.payload().value
+    }
+
+    pub fn mode(&self) -> QueueMode {
+        self.payload().mode
+    }
+
+    /// The same order, aimed at `target`.
+    pub fn aimed_at(self, target: OrderTarget) -> Self {
+        let payload = Queued::new(target, self.mode());
+        match self {
+            OrderCommand::Smart(_)      => OrderCommand::Smart(payload),
+            OrderCommand::AttackMove(_) => OrderCommand::AttackMove(payload),
+            OrderCommand::Patrol(_)     => OrderCommand::Patrol(payload),
+        }
+    }
+
+    /// The `UnitCommand` the order stands for, `None` until it is aimed.
+    pub fn unit_command(&self) -> Option<UnitCommand> {
+        let mode = self.mode();
+        match (self, self.target()) {
+            (_, OrderTarget::Cursor) => None,
+            (OrderCommand::Smart(_) | OrderCommand::AttackMove(_), OrderTarget::Entity(entity)) => {
+                Some(UnitCommand::Attack { entity: Queued::new(entity, mode) })
+            }
+            (OrderCommand::Smart(_), OrderTarget::Ground(target)) => {
+                Some(UnitCommand::Move { target: Queued::new(target, mode) })
+            }
+            (OrderCommand::AttackMove(_), OrderTarget::Ground(target)) => {
+                Some(UnitCommand::AttackMove { target: Queued::new(target, mode) })
+            }
+            (OrderCommand::Patrol(_), OrderTarget::Ground(target)) => {
+                Some(UnitCommand::Patrol { waypoints: Queued::new(vec![target], mode) })
+            }
+            (OrderCommand::Patrol(_), OrderTarget::Entity(_)) => None,
+        }
+    }
+}
+
+/// What an `OrderCommand` is aimed at, written `4, 0, -2` for a point of the ground
+/// and as its bits for an entity.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum OrderTarget {
+    /// Whatever is under the cursor, as fired by the bindings.
+    #[default]
+    Cursor,
+    Ground(Vec3),
+    Entity(Entity),
+}
+
+impl CommandPayload for OrderTarget {
+    fn default_payload() -> Self {
+        OrderTarget::Cursor
+    }
+
+    fn parse_payload(payload: Option<&str>) -> Option<Self> {
+        match payload {
+            None => Some(OrderTarget::Cursor),
+            Some(payload) if payload.contains(',') => Vec3::parse_payload(Some(payload)).map(OrderTarget::Ground),
+            Some(payload) => Entity::parse_payload(Some(payload)).map(OrderTarget::Entity),
+        }
+    }
+
+    fn write_payload(&self, f: &mut Formatter<'_>, name: &str) -> fmt::Result {
+        match self {
+            OrderTarget::Cursor         => write!(f, "{}", name),
+            OrderTarget::Ground(point)  => point.write_payload(f, name),
+            OrderTarget::Entity(entity) => entity.write_payload(f, name),
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        other
+    }
+
+    fn encode_payload(&self, writer: &mut WireWriter) {
+        match self {
+            OrderTarget::Cursor => writer.write_u8(0),
+            OrderTarget::Ground(point) => {
+                writer.write_u8(1);
+                point.encode_payload(writer);
+            }
+            OrderTarget::Entity(entity) => {
+                writer.write_u8(2);
+                entity.encode_payload(writer);
+            }
+        }
+    }
+
+    fn decode_payload(reader: &mut WireReader) -> Result<Self, WireError> {
+        match reader.read_u8()? {
+            0 => Ok(OrderTarget::Cursor),
+            1 => Vec3::decode_payload(reader).map(OrderTarget::Ground),
+            2 => Entity::decode_payload(reader).map(OrderTarget::Entity),
+            tag => Err(WireError::InvalidTag(tag)),
+        }
+    }
+}
+
+const DEFAULT_UNIT_SPEED: f32 = 3.0;
+/// Distance from its target at which a unit stops to attack it.
+const ATTACK_RANGE: f32 = 1.5;
+
+/// An entity following the orders of its `OrderQueue`, moving on the ground at
+/// `speed` units per second.
+#[derive(Component, Clone, Copy)]
+#[require(Order, OrderQueue<UnitCommand>, Navigation)]
+pub struct Unit {
+    pub speed: f32,
+}
+
+impl Default for Unit {
+    fn default() -> Self {
+        Self { speed: DEFAULT_UNIT_SPEED }
+    }
+}
+
+/// The order a `Unit` is carrying out, started from the front of its `OrderQueue`.
+#[derive(Component, Clone, Debug, PartialEq, Default)]
+pub enum Order {
+    #[default]
+    Idle,
+    Move(Vec3),
+    AttackMove(Vec3),
+    Attack(Entity),
+    HoldPosition,
+    Patrol { waypoints: Vec<Vec3>, next: usize },
+}
+
+impl Order {
+    /// The order `command` gives to a unit standing at `position`.
+    pub fn from_command(command: &UnitCommand, position: Vec3) -> Self {
+        match command {
+            UnitCommand::Move { target }       => Order::Move(target.value),
+            UnitCommand::AttackMove { target } => Order::AttackMove(target.value),
+            UnitCommand::Attack { entity }     => Order::Attack(entity.value),
+            UnitCommand::Stop { .. }           => Order::Idle,
+            UnitCommand::HoldPosition { .. }   => Order::HoldPosition,
+            UnitCommand::Patrol { waypoints }  => Order::Patrol {
+                waypoints: std::iter::once(position).chain(waypoints.value.iter().copied()).collect(),
+                next: 1,
+            },
+        }
+    }
+}
+
+/// The `UnitCommand`s dispatched this frame, and the ones aimed `OrderCommand`s
+/// stand for, given to the selected units. Each is appended to the `OrderQueue` of
+/// the units or replaces it, as its `QueueMode` says.
+#[derive(Resource, Default)]
+pub struct UnitOrders {
+    pending: Vec<UnitCommand>,
+}
+
+impl CommandHandler<UnitCommand> for UnitOrders {
+    fn handle_commands(&mut self, commands: &CommandSet<UnitCommand>) {
+        self.pending.extend(commands.iter().cloned());
+    }
+}
+
+impl CommandHandler<OrderCommand> for UnitOrders {
+    fn handle_commands(&mut self, commands: &CommandSet<OrderCommand>) {
+        self.pending.extend(commands.iter().filter_map(OrderCommand::unit_command));
+    }
+}
+
+impl ExtCommandHandler for UnitOrders {
+    fn handle_ext_commands(&mut self, commands: &[&dyn Command]) {
+        self.handle_commands(&downcast_commands::<UnitCommand>(commands));
+        self.handle_commands(&downcast_commands::<OrderCommand>(commands));
+    }
+}
+
+/// `order` aimed at what the cursor casting `ray` is over, among the `Selectable`
+/// candidates.
+pub fn aim_order(
+    order: OrderCommand,
+    ray: Ray3d,
+    selection: &Selection,
+    candidates: impl IntoIterator<Item = (Entity, Vec3, f32)>,
+) -> Option<OrderCommand> {
+    if let OrderCommand::Smart(_) = order
+        && let Some(entity) = pick(ray, candidates).filter(|&entity| !selection.contains(entity))
+    {
+        return Some(order.aimed_at(OrderTarget::Entity(entity)));
+    }
+
+    let point = ray.get_point(ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y))?);
+    Some(order.aimed_at(OrderTarget::Ground(point)))
+}
+
+/// Gives `UnitCommand`s to the selected `Unit`s and moves them until their queued
+/// orders are carried out, drawing the waypoints of the selected ones when gizmos are
+/// available. Needs the `SelectionPlugin`. With the `PathfindingPlugin`, units follow
+/// the route of their `Navigation` rather than a straight line.
+pub struct UnitPlugin;
+
+impl Plugin for UnitPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(InputPlugin::<UnitCommand>::default())
+            .add_plugins(InputPlugin::<OrderCommand>::default())
+            .add_plugins(OrderQueuePlugin::<UnitCommand>::default())
+            .register_command::<UnitCommand>()
+            .register_command::<OrderCommand>()
+            .init_resource::<UnitOrders>()
+            .add_command_handler::<UnitOrders>("unit.*")
+            .add_command_handler::<UnitOrders>("order.*")
+            .add_systems(PreUpdate, aim_pointer_orders.after(InputSet))
+            .add_systems(
+                Update,
+                (
+                    apply_unit_orders.after(CommandDispatchSet).before(OrderQueueSet),
+                    (start_unit_orders, navigate_units).chain().after(OrderQueueSet).before(PathfindingSet),
+                    move_units.after(navigate_units).after(PathfindingSet),
+                    draw_queued_orders.after(move_units).run_if(resource_exists::<GizmoConfigStore>),
+                ),
+            );
+    }
+}
+
+/// Aims the orders fired by the bindings at what is under the cursor, dropping them
+/// when the cursor is not over the window.
+fn aim_pointer_orders(
+    mut queue: ResMut<CommandSystem>,
+    selection: Res<Selection>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    selectables: Query<(Entity, &GlobalTransform, &Selectable)>,
+) {
+    let orders = queue.take_commands::<OrderCommand>();
+    let ray = windows.single().ok().and_then(Window::cursor_position).and_then(|position| {
+        let (camera, transform) = cameras.single().ok()?;
+        camera.viewport_to_world(transform, position).ok()
+    });
+
+    let candidates = || {
+        selectables
+            .iter()
+            .map(|(entity, transform, selectable)| (entity, transform.translation(), selectable.radius))
+    };
+    for order in orders {
+        let aimed = match (order.target(), ray) {
+            (OrderTarget::Cursor, Some(ray)) => aim_order(order, ray, &selection, candidates()),
+            (OrderTarget::Cursor, None) => None,
+            _ => Some(order),
+        };
+        if let Some(order) = aimed {
+            queue.push_command(order);
+        }
+    }
+}
+
+fn apply_unit_orders(
+    mut orders: ResMut<UnitOrders>,
+    selection: Res<Selection>,
+    mut units: Query<&mut OrderQueue<UnitCommand>, With<Unit>>,
+) {
+    for command in std::mem::take(&mut orders.pending) {
+        for &entity in selection.entities() {
+            if let Ok(mut queue) = units.get_mut(entity) {
+                queue.push(command.mode(), command.clone());
+            }
+        }
+    }
+}
+
+fn start_unit_orders(
+    mut started: EventReader<OrderStarted<UnitCommand>>,
+    mut completed: EventWriter<OrderCompleted<UnitCommand>>,
+    mut units: Query<(&Transform, &mut Order)>,
+) {
+    for OrderStarted { entity, order: command } in started.read() {
+        if let Ok((transform, mut order)) = units.get_mut(*entity) {
+            *order = Order::from_command(command, transform.translation);
+            // Stopping is done as soon as it starts, unlike holding the position
+            if let UnitCommand::Stop { .. } = command {
+                completed.write(OrderCompleted::new(*entity));
+            }
+        }
+    }
+}
+
+/// The point a unit carrying out `order` heads to, `None` if it stays where it is.
+fn destination(order: &Order, targets: &Query<&GlobalTransform>) -> Option<Vec3> {
+    match order {
+        Order::Move(target) | Order::AttackMove(target) => Some(*target),
+        Order::Patrol { waypoints, next } => waypoints.get(*next).copied(),
+        Order::Attack(enemy) => targets.get(*enemy).ok().map(GlobalTransform::translation),
+        Order::Idle | Order::HoldPosition => None,
+    }
+}
+
+fn navigate_units(mut units: Query<(&Order, &mut Navigation)>, targets: Query<&GlobalTransform>) {
+    for (order, mut navigation) in &mut units {
+        let destination = destination(order, &targets);
+        if navigation.destination() != destination {
+            navigation.set_destination(destination);
+        }
+    }
+}
+
+fn move_units(
+    time: Res<Time>,
+    mut units: Query<(Entity, &Unit, &mut Order, &Navigation, &mut Transform)>,
+    targets: Query<&GlobalTransform>,
+    mut completed: EventWriter<OrderCompleted<UnitCommand>>,
+) {
+    let dt = time.delta_secs();
+    for (entity, unit, mut order, navigation, mut transform) in &mut units {
+        let (target, range) = match &*order {
+            Order::Move(target) | Order::AttackMove(target) => (*target, 0.0),
+            Order::Patrol { waypoints, next } => match waypoints.get(*next) {
+                Some(waypoint) => (*waypoint, 0.0),
+                None => continue,
+            },
+            Order::Attack(enemy) => match targets.get(*enemy) {
+                Ok(target) => (target.translation(), ATTACK_RANGE),
+                Err(_) => {
+                    *order = Order::Idle;
+                    completed.write(OrderCompleted::new(entity));
+                    continue;
+                }
+            },
+            Order::Idle | Order::HoldPosition => continue,
+        };
+
+        // Without a route, units head straight to their target
+        let heading = match navigation.status() {
+            NavStatus::Idle => Some(target),
+            NavStatus::Planning => continue,
+            NavStatus::Moving(waypoint) => Some(waypoint),
+            NavStatus::Arrived | NavStatus::Unreachable => None,
+        };
+
+        if let Some(heading) = heading {
+            // Units move on the ground, keeping their height
+            let flat = |point: Vec3| Vec3::new(point.x - transform.translation.x, 0.0, point.z - transform.translation.z);
+            let remaining = (flat(target).length() - range).max(0.0);
+            let offset = flat(heading);
+            let step = (unit.speed * dt).min(remaining).min(offset.length());
+            transform.translation += offset.normalize_or_zero() * step;
+            if step < remaining {
+                continue;
+            }
+        }
+
+        match &mut *order {
+            Order::Move(_) | Order::AttackMove(_) => {
+                *order = Order::Idle;
+                completed.write(OrderCompleted::new(entity));
+            }
+            Order::Patrol { waypoints, next } => *next = (*next + 1) % waypoints.len(),
+            Order::Attack(_) if navigation.status() == NavStatus::Unreachable => {
+                *order = Order::Idle;
+                completed.write(OrderCompleted::new(entity));
+            }
+            _ => {}
+        }
+    }
+}
+
+const MOVE_COLOR: Color = Color::srgb(0.2, 0.9, 0.3);
+const ATTACK_COLOR: Color = Color::srgb(0.9, 0.2, 0.2);
+const PATROL_COLOR: Color = Color::srgb(0.9, 0.8, 0.2);
+
+/// Draws the path through the queued orders of the selected units, on the ground.
+fn draw_queued_orders(
+    selection: Res<Selection>,
+    units: Query<(&Transform, &OrderQueue<UnitCommand>), With<Unit>>,
+    targets: Query<&GlobalTransform>,
+    mut gizmos: Gizmos,
+) {
+    for &entity in selection.entities() {
+        let Ok((transform, queue)) = units.get(entity) else {
+            continue;
+        };
+
+        let mut from = transform.translation.with_y(0.0);
+        for command in queue.iter() {
+            let (points, color) = match command {
+                UnitCommand::Move { target }       => (vec![target.value], MOVE_COLOR),
+                UnitCommand::AttackMove { target } => (vec![target.value], ATTACK_COLOR),
+                UnitCommand::Attack { entity }     => {
+                    (targets.get(entity.value).map(|target| target.translation().with_y(0.0)).into_iter().collect(), ATTACK_COLOR)
+                }
+                UnitCommand::Patrol { waypoints }  => (waypoints.value.clone(), PATROL_COLOR),
+                UnitCommand::Stop { .. } | UnitCommand::HoldPosition { .. } => continue,
+            };
+            for to in points {
+                gizmos.line(from, to, color);
+                from = to;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::command::registry::CommandRegistry;
+    use crate::core::input::profile::BindingProfile;
+    use crate::core::selection::{SelectMode, SelectionPlugin};
+    use crate::core::testing::TestApp;
+    use crate::core::FromString;
+    use bevy::math::Dir3;
+    use bevy::prelude::{KeyCode, MouseButton};
+
+    fn app() -> (TestApp, [Entity; 2]) {
+        let mut app = TestApp::new((SelectionPlugin, UnitPlugin));
+        let units = [Vec3::new(0.0, 0.5, 0.0), Vec3::new(2.0, 0.5, 0.0)].map(|position| {
+            let transform = Transform::from_translation(position);
+            app.world_mut().spawn((Unit::default(), Selectable::default(), transform, GlobalTransform::from(transform))).id()
+        });
+        app.world_mut().resource_mut::<Selection>().select(SelectMode::Replace, [units[0]]);
+        (app, units)
+    }
+
+    fn order(app: &mut TestApp, command: UnitCommand) {
+        app.world_mut().resource_mut::<CommandSystem>().push_command(command);
+    }
+
+    fn position(app: &TestApp, entity: Entity) -> Vec3 {
+        app.world().get::<Transform>(entity).unwrap().translation
+    }
+
+    #[test]
+    fn orders_apply_to_the_selection() {
+        let (mut app, [selected, other]) = app();
+        order(&mut app, UnitCommand::Move { target: Vec3::new(0.0, 0.0, 6.0).into() });
+        app.run(60);
+
+        assert!(position(&app, selected).distance(Vec3::new(0.0, 0.5, 3.0)) < 1e-3);
+        assert_eq!(position(&app, other), Vec3::new(2.0, 0.5, 0.0));
+
+        order(&mut app, UnitCommand::Stop { mode: QueueMode::Replace });
+        app.run(10);
+        assert_eq!(app.world().get::<Order>(selected), Some(&Order::Idle));
+        assert!(position(&app, selected).distance(Vec3::new(0.0, 0.5, 3.0)) < 0.1);
+
+        order(&mut app, UnitCommand::Attack { entity: other.into() });
+        app.run(60);
+        assert!((position(&app, selected).distance(position(&app, other)) - ATTACK_RANGE).abs() < 1e-3);
+    }
+
+    #[test]
+    fn patrols_go_back_and_forth() {
+        let (mut app, [selected, _]) = app();
+        order(&mut app, UnitCommand::Patrol { waypoints: vec![Vec3::new(3.0, 0.0, 0.0)].into() });
+        app.run(61);
+        assert!(position(&app, selected).distance(Vec3::new(3.0, 0.5, 0.0)) < 0.1);
+
+        app.run(60);
+        assert!(position(&app, selected).distance(Vec3::new(0.0, 0.5, 0.0)) < 0.1);
+        app.run(30);
+        assert!(position(&app, selected).distance(Vec3::new(1.5, 0.5, 0.0)) < 0.1);
+        assert!(matches!(app.world().get::<Order>(selected), Some(Order::Patrol { next: 1, .. })));
+    }
+
+    #[test]
+    fn appended_orders_are_queued() {
+        let (mut app, [selected, _]) = app();
+        order(&mut app, UnitCommand::Move { target: Vec3::new(3.0, 0.0, 0.0).into() });
+        app.update();
+        order(&mut app, UnitCommand::Move { target: Queued::new(Vec3::new(3.0, 0.0, 3.0), QueueMode::Append) });
+        order(&mut app, UnitCommand::HoldPosition { mode: QueueMode::Append });
+        app.update();
+        assert_eq!(app.world().get::<OrderQueue<UnitCommand>>(selected).unwrap().iter().count(), 3);
+
+        app.run(60);
+        assert!(position(&app, selected).distance(Vec3::new(3.0, 0.5, 0.0)) < 0.1);
+        app.run(61);
+        assert!(position(&app, selected).distance(Vec3::new(3.0, 0.5, 3.0)) < 1e-3);
+        assert_eq!(app.world().get::<Order>(selected), Some(&Order::HoldPosition));
+
+        // Without `append`, the order replaces the queue even with Shift held
+        app.press(KeyCode::ShiftLeft);
+        order(&mut app, UnitCommand::Stop { mode: QueueMode::Replace });
+        app.run(2);
+        assert_eq!(app.world().get::<OrderQueue<UnitCommand>>(selected).unwrap().iter().count(), 0);
+        assert_eq!(app.world().get::<Order>(selected), Some(&Order::Idle));
+    }
+
+    #[test]
+    fn bindings_choose_the_queue_mode() {
+        let (mut app, [selected, _]) = app();
+        let profile =
+            BindingProfile::parse("unit.holdPosition = pressed: H\nunit.holdPosition(append) = pressed: Shift + H").unwrap();
+        app.world_mut().insert_resource(profile);
+        order(&mut app, UnitCommand::Move { target: Vec3::new(3.0, 0.0, 0.0).into() });
+        app.update();
+
+        app.press(KeyCode::ShiftLeft);
+        app.press(KeyCode::KeyH);
+        app.update();
+        assert_eq!(app.last_commands(), ["unit.holdPosition(append)"]);
+        assert_eq!(app.world().get::<OrderQueue<UnitCommand>>(selected).unwrap().iter().count(), 2);
+
+        app.release(KeyCode::KeyH);
+        app.release(KeyCode::ShiftLeft);
+        app.update();
+        app.press(KeyCode::KeyH);
+        app.update();
+        assert_eq!(app.last_commands(), ["unit.holdPosition"]);
+        assert_eq!(app.world().get::<OrderQueue<UnitCommand>>(selected).unwrap().iter().count(), 1);
+    }
+
+    #[test]
+    fn right_click_orders_what_is_under_the_cursor() {
+        let (mut app, [selected, other]) = app();
+        // Orders cannot be aimed without the cursor over the window
+        app.press_mouse(MouseButton::Right);
+        app.update();
+        assert!(app.last_commands().is_empty());
+
+        let mut selection = Selection::default();
+        selection.select(SelectMode::Replace, [selected]);
+        let ray = |x: f32| Ray3d::new(Vec3::new(x, 10.0, 0.0), Dir3::NEG_Y);
+        let candidates = [(selected, Vec3::ZERO, 0.5), (other, Vec3::new(2.0, 0.0, 0.0), 0.5)];
+        let smart = OrderCommand::Smart(Queued::new(OrderTarget::Cursor, QueueMode::Append));
+
+        let aimed = |x: f32| aim_order(smart, ray(x), &selection, candidates).map(|order| order.target());
+        assert_eq!(aimed(2.0), Some(OrderTarget::Entity(other)));
+        assert_eq!(aimed(0.0), Some(OrderTarget::Ground(Vec3::ZERO)));
+        let patrol = aim_order(OrderCommand::Patrol(OrderTarget::Cursor.into()), ray(2.0), &selection, candidates).unwrap();
+        assert_eq!(patrol.unit_command(), Some(UnitCommand::Patrol { waypoints: vec![Vec3::new(2.0, 0.0, 0.0)].into() }));
+        let attack = aim_order(smart, ray(2.0), &selection, candidates).and_then(|order| order.unit_command());
+        assert_eq!(attack, Some(UnitCommand::Attack { entity: Queued::new(other, QueueMode::Append) }));
+        assert_eq!(smart.unit_command(), None);
+
+        // Aimed orders go to the selection as they are
+        app.world_mut().resource_mut::<CommandSystem>().push_command(OrderCommand::Smart(OrderTarget::Entity(other).into()));
+        app.update();
+        assert_eq!(app.last_commands(), [format!("order.smart({})", other.to_bits())]);
+        assert_eq!(app.world().get::<Order>(selected), Some(&Order::Attack(other)));
+    }
+
+    #[test]
+    fn orders_round_trip() {
+        let mut registry = CommandRegistry::default();
+        registry.register::<UnitCommand>();
+        let patrol = UnitCommand::Patrol { waypoints: vec![Vec3::new(1.0, 0.0, 2.5), Vec3::new(-4.0, 0.0, 0.0)].into() };
+        let attack = UnitCommand::Attack { entity: Queued::new(Entity::from_raw(42), QueueMode::Append) };
+        let hold = UnitCommand::HoldPosition { mode: QueueMode::Append };
+
+        assert_eq!(patrol.to_string(), "unit.patrol(1, 0, 2.5; -4, 0, 0)");
+        assert_eq!(UnitCommand::from_string(&patrol.to_string()), Some(patrol.clone()));
+        let replacing = UnitCommand::Move { target: Vec3::new(4.0, 0.0, -2.0).into() };
+        assert_eq!(UnitCommand::from_string("unit.move(4, 0, -2)"), Some(replacing));
+        let appended = UnitCommand::Move { target: Queued::new(Vec3::new(4.0, 0.0, -2.0), QueueMode::Append) };
+        assert_eq!(appended.to_string(), "unit.move(4, 0, -2; append)");
+        assert_eq!(UnitCommand::from_string("unit.move(4, 0, -2; append)"), Some(appended));
+        assert_eq!(hold.to_string(), "unit.holdPosition(append)");
+        assert_eq!(UnitCommand::from_string("unit.stop"), Some(UnitCommand::Stop { mode: QueueMode::Replace }));
+        assert_eq!(UnitCommand::from_string("unit.move"), None);
+        assert_eq!(UnitCommand::from_string("unit.stop(later)"), None);
+
+        let smart = OrderCommand::Smart(OrderTarget::Ground(Vec3::new(4.0, 0.0, -2.0)).into());
+        let attack_move = OrderCommand::AttackMove(Queued::new(OrderTarget::Entity(Entity::from_raw(7)), QueueMode::Append));
+        assert_eq!(smart.to_string(), "order.smart(4, 0, -2)");
+        assert_eq!(OrderCommand::from_string(&attack_move.to_string()), Some(attack_move));
+        assert_eq!(OrderCommand::from_string("order.patrol"), Some(OrderCommand::Patrol(OrderTarget::Cursor.into())));
+        let patrol_appended = OrderCommand::Patrol(Queued::new(OrderTarget::Cursor, QueueMode::Append));
+        assert_eq!(patrol_appended.to_string(), "order.patrol(append)");
+        assert_eq!(OrderCommand::from_string("order.patrol(append)"), Some(patrol_appended));
+
+        registry.register::<OrderCommand>();
+        let commands: [&dyn Command; 5] = [&patrol, &attack, &hold, &smart, &attack_move];
+        let decoded = registry.decode(&registry.encode(&commands).unwrap()).unwrap();
+        let decoded: Vec<String> = decoded.iter().map(|command| command.to_string()).collect();
+        let expected: Vec<String> = commands.iter().map(|command| command.to_string()).collect();
+        assert_eq!(decoded, expected);
+    }
+}
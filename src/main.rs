@@ -1,18 +1,165 @@
 mod core;
 
-use bevy::input::mouse::MouseMotion;
 use bevy::prelude::*;
 use bevy::math::primitives::{Cuboid, Plane3d};
-use crate::core::*;
-use crate::core::input::{ KeyBinding };
+use crate::core::camera::{CameraPlugin, CameraSystem, EdgeScroll};
+use crate::core::cursor::CursorPlugin;
+use crate::core::group::ControlGroupPlugin;
+use crate::core::command::registry::CommandRegistry;
+use crate::core::command::replay::{CommandRecorder, Recording};
+use crate::core::input::capture::{BindingCapture, BindingCaptured, CaptureResult};
+use crate::core::input::context::{InputContext, InputContextAppExt};
+use crate::core::input::gamepad::{ResponseCurve, StickSettings};
+use crate::core::input::keyboard::KeyboardLayout;
+use crate::core::input::profile::{ BindingProfile, ProfileError };
+use crate::core::FromString;
+use crate::core::pathfinding::{NavTerrain, Obstacle, PathfindingPlugin};
+use crate::core::selection::{Selectable, SelectionPlugin, SelectionSystem};
+use crate::core::unit::{Unit, UnitPlugin};
+
+const BINDINGS_PATH: &str = "bindings.cfg";
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
-        .add_systems(Startup, setup)
-        .add_systems(Update, rotate_cube)
-        .add_systems(Update, move_camera)
-        .run();
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins)
+        .add_plugins(CameraPlugin)
+        .add_plugins(CursorPlugin)
+        .add_plugins(SelectionPlugin)
+        .add_plugins(ControlGroupPlugin)
+        .add_plugins(UnitPlugin)
+        .add_plugins(PathfindingPlugin)
+        .insert_resource(
+            CameraSystem::with_speed(5.0)
+                .with_height_limits(1.0, 12.0)
+                .with_edge_scroll(EdgeScroll { lock_cursor: true, ..default() }),
+        )
+        .insert_resource(StickSettings { curve: stick_curve(), ..default() })
+        .init_state::<Screen>()
+        .add_state_input_context(Screen::Rebinding, InputContext::Menu)
+        .add_systems(Startup, (setup, start_replay, start_rebind))
+        .add_systems(Update, (rotate_cube, draw_selection_box, apply_rebind, report_replay_end));
+
+    let profile = load_binding_profile(app.world().resource::<CommandRegistry>());
+    app.insert_resource(profile).run();
+}
+
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+enum Screen {
+    #[default]
+    Playing,
+    /// Waiting for the binding asked for with `--rebind`, the pointer kept from gameplay.
+    Rebinding,
+}
+
+/// The value following `flag` on the command line
+fn argument(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2).find(|pair| pair[0] == flag).map(|pair| pair[1].clone())
+}
+
+/// Load the player's binding profile, checking its commands against `registry`, and
+/// fall back to the shipped one for the keyboard layout
+fn load_binding_profile(registry: &CommandRegistry) -> BindingProfile {
+    match BindingProfile::load(BINDINGS_PATH, registry) {
+        Ok(profile) => profile,
+        Err(ProfileError::Io(_)) => BindingProfile::for_layout(keyboard_layout()),
+        Err(error) => {
+            eprintln!("{}: {}", BINDINGS_PATH, error);
+            BindingProfile::for_layout(keyboard_layout())
+        }
+    }
+}
+
+/// The layout chosen with `--layout Azerty`, QWERTY by default
+fn keyboard_layout() -> KeyboardLayout {
+    match argument("--layout") {
+        Some(layout) => KeyboardLayout::from_string(&layout).unwrap_or_else(|| {
+            eprintln!("--layout: unknown keyboard layout `{}`", layout);
+            KeyboardLayout::default()
+        }),
+        None => KeyboardLayout::default(),
+    }
+}
+
+/// The stick response chosen with `--stick-curve Cubic`, quadratic by default
+fn stick_curve() -> ResponseCurve {
+    let default = StickSettings::default().curve;
+    match argument("--stick-curve") {
+        Some(curve) => ResponseCurve::from_string(&curve).unwrap_or_else(|| {
+            eprintln!("--stick-curve: unknown response curve `{}`", curve);
+            default
+        }),
+        None => default,
+    }
+}
+
+/// Start recording with `--record <file>` or replaying with `--replay <file>`
+fn start_replay(mut recorder: ResMut<CommandRecorder>, registry: Res<CommandRegistry>) {
+    let args: Vec<String> = std::env::args().collect();
+    for pair in args.windows(2) {
+        match pair[0].as_str() {
+            "--record" => recorder.record_to(&pair[1]),
+            "--replay" => match Recording::load(&pair[1]) {
+                Ok(recording) => match recording.commands(&registry) {
+                    Ok(_) => recorder.play(recording),
+                    Err(error) => eprintln!("{}: {}", pair[1], error),
+                },
+                Err(error) => eprintln!("{}: {}", pair[1], error),
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Tell when a replay gives control back to live input
+fn report_replay_end(recorder: Res<CommandRecorder>, mut was_playing: Local<bool>) {
+    if *was_playing && !recorder.is_playing() {
+        info!("replay finished");
+    }
+    *was_playing = recorder.is_playing();
+}
+
+/// Listen for a new binding of the command given with `--rebind <command>`
+fn start_rebind(
+    registry: Res<CommandRegistry>,
+    profile: Res<BindingProfile>,
+    mut capture: ResMut<BindingCapture>,
+    mut screen: ResMut<NextState<Screen>>,
+) {
+    let Some(command) = argument("--rebind") else {
+        return;
+    };
+    if registry.parse(&command).is_none() {
+        let names: Vec<&str> = registry.names().collect();
+        eprintln!("--rebind: unknown command `{}`, expected one of {}", command, names.join(", "));
+        return;
+    }
+
+    let bindings: Vec<String> =
+        profile.bindings(&command).unwrap_or_default().iter().map(ToString::to_string).collect();
+    info!("press the new binding of {} (now {}), or Escape to keep it", command, bindings.join(", "));
+    capture.listen(&command);
+    screen.set(Screen::Rebinding);
+}
+
+/// Save the binding captured for `--rebind` to the player's profile
+fn apply_rebind(
+    mut captured: EventReader<BindingCaptured>,
+    mut profile: ResMut<BindingProfile>,
+    mut screen: ResMut<NextState<Screen>>,
+) {
+    for BindingCaptured { command, result } in captured.read() {
+        if let CaptureResult::Captured { binding, conflicts } = result {
+            if !conflicts.is_empty() {
+                warn!("{} is also bound to {}", binding, conflicts.join(", "));
+            }
+            profile.set_bindings(command, vec![binding.clone().into()]);
+            if let Err(error) = profile.save(BINDINGS_PATH) {
+                eprintln!("{}: {}", BINDINGS_PATH, error);
+            }
+        }
+        screen.set(Screen::Playing);
+    }
 }
 
 fn setup(
@@ -20,11 +167,6 @@ fn setup(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    let binding = KeyBinding::from_string("Ctrl + A");
-    if let Some(binding) = binding {
-        println!("binding: {}", binding);
-    }
-
     // Camera
     commands.spawn((
         Camera3d::default(),
@@ -62,6 +204,38 @@ fn setup(
         GlobalTransform::default(),
         Visibility::default(),
         Rotates,
+        Selectable::new(0.9),
+        Obstacle::new(Vec2::ONE),
+    ));
+
+    // Units
+    let unit_mesh = meshes.add(Cuboid::from_size(Vec3::splat(0.4)).mesh());
+    let unit_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.9, 0.6, 0.2),
+        ..default()
+    });
+    for index in 0..5 {
+        commands.spawn((
+            Mesh3d(unit_mesh.clone()),
+            MeshMaterial3d(unit_material.clone()),
+            Transform::from_xyz(-2.0 + index as f32, 0.2, 2.5),
+            GlobalTransform::default(),
+            Visibility::default(),
+            Unit::default(),
+            Selectable::new(0.35),
+        ));
+    }
+
+    // Selection box
+    commands.spawn((
+        Node {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            border: UiRect::all(Val::Px(1.0)),
+            ..default()
+        },
+        BorderColor(Color::WHITE),
+        SelectionBox,
     ));
 
     // Ground plane
@@ -74,166 +248,35 @@ fn setup(
         Transform::from_xyz(0.0, 0.0, 0.0),
         GlobalTransform::default(),
         Visibility::default(),
+        NavTerrain::new(Vec2::splat(10.0)),
     ));
 }
 
 #[derive(Component)]
 struct Rotates;
 
-fn rotate_cube(time: Res<Time>, mut query: Query<&mut Transform, With<Rotates>>) {
-    let dt = time.delta().as_secs_f32();
-    for mut transform in &mut query {
-        transform.rotate_y(1.0 * dt);
-    }
-}
-
-fn move_camera(
-    keys: Res<ButtonInput<KeyCode>>,
-    window_query: Query<&Window>,
-    mut mouse_motion_events: EventReader<MouseMotion>,
-    buttons: Res<ButtonInput<MouseButton>>,
-    mut query: Query<&mut Transform, With<Camera3d>>,
-    time: Res<Time>,
-) {
-    let dt = time.delta().as_secs_f32();
-    const SPEED: f32 = 5.0;
-    const ROTATION_SPEED: f32 = 0.002;
-
-    // Detect movement flags based on input
-    let keyboard_movement = detect_keyboard_input(&keys);
-    let (mouse_movement, y_rotation) = detect_mouse_input(&window_query, &mut mouse_motion_events, &buttons);
-
-    // Update camera position based on movement flags
-    update_camera(
-        keyboard_movement | mouse_movement,
-        y_rotation * ROTATION_SPEED,
-        &mut query,
-        SPEED,
-        dt,
-    );
-}
-
-/// Detect movement flags based on keyboard input
-fn detect_keyboard_input(keys: &Res<ButtonInput<KeyCode>>) -> u8 {
-    // Define movement bits (bitflags could also be used for clarity)
-    const MOVE_FORWARD: u8 = 1 << 0;
-    const MOVE_BACKWARD: u8 = 1 << 1;
-    const MOVE_LEFT: u8 = 1 << 2;
-    const MOVE_RIGHT: u8 = 1 << 3;
-
-    let mut movement_flags = 0;
-
-    if keys.pressed(KeyCode::ArrowUp) || keys.pressed(KeyCode::KeyZ) {
-        movement_flags |= MOVE_FORWARD;
-    }
-    if keys.pressed(KeyCode::ArrowDown) || keys.pressed(KeyCode::KeyS) {
-        movement_flags |= MOVE_BACKWARD;
-    }
-    if keys.pressed(KeyCode::ArrowLeft) || keys.pressed(KeyCode::KeyQ) {
-        movement_flags |= MOVE_LEFT;
-    }
-    if keys.pressed(KeyCode::ArrowRight) || keys.pressed(KeyCode::KeyD) {
-        movement_flags |= MOVE_RIGHT;
-    }
-
-    movement_flags
-}
-
-/// Detect movement flags based on mouse position near screen edges
-fn detect_mouse_input(
-    window_query: &Query<&Window>,
-    mouse_motion_events: &mut EventReader<MouseMotion>,
-    buttons: &Res<ButtonInput<MouseButton>>
-) -> (u8, f32) {
-    const MOVE_FORWARD: u8 = 1 << 0;
-    const MOVE_BACKWARD: u8 = 1 << 1;
-    const MOVE_LEFT: u8 = 1 << 2;
-    const MOVE_RIGHT: u8 = 1 << 3;
-
-    let mut movement_flags = 0;
-    let mut y_rotation = 0.0;
-
-    if buttons.pressed(MouseButton::Middle) {
-        for event in mouse_motion_events.read() {
-            println!("Mouse motion delta: {:?}", event.delta.x);
-            y_rotation -= event.delta.x;
-        }
-    }
-    else
-    {
-        if let Ok(window) = window_query.single() {
-            const BORDER_THRESHOLD: f32 = 50.0;
-            if let Some(cursor_position) = window.cursor_position() {
-                let width = window.resolution.width() as f32;
-                let height = window.resolution.height() as f32;
-
-                if cursor_position.y >= height - BORDER_THRESHOLD {
-                    movement_flags |= MOVE_BACKWARD;
-                }
-                if cursor_position.y <= BORDER_THRESHOLD {
-                    movement_flags |= MOVE_FORWARD;
-                }
-                if cursor_position.x <= BORDER_THRESHOLD {
-                    movement_flags |= MOVE_LEFT;
-                }
-                if cursor_position.x >= width - BORDER_THRESHOLD {
-                    movement_flags |= MOVE_RIGHT;
-                }
+/// The outline of the box being dragged to select units
+#[derive(Component)]
+struct SelectionBox;
+
+fn draw_selection_box(selection: Res<SelectionSystem>, mut boxes: Query<&mut Node, With<SelectionBox>>) {
+    for mut node in &mut boxes {
+        match selection.drag_rect() {
+            Some(rect) => {
+                node.display = Display::Flex;
+                node.left = Val::Px(rect.min.x);
+                node.top = Val::Px(rect.min.y);
+                node.width = Val::Px(rect.width());
+                node.height = Val::Px(rect.height());
             }
+            None => node.display = Display::None,
         }
     }
-
-    (movement_flags, y_rotation)
 }
 
-
-/// Update the camera's position based on movement flags
-fn update_camera(
-    movement_flags: u8,
-    y_rotation: f32,
-    query: &mut Query<&mut Transform, With<Camera3d>>,
-    speed: f32,
-    dt: f32,
-) {
-    // Define movement bits
-    const MOVE_FORWARD: u8 = 1 << 0;
-    const MOVE_BACKWARD: u8 = 1 << 1;
-    const MOVE_LEFT: u8 = 1 << 2;
-    const MOVE_RIGHT: u8 = 1 << 3;
-
-    for mut transform in query.iter_mut() {
-        // Compute forward and right vectors projected on the X-Z plane
-        let forward = transform.forward();
-        let right = transform.right();
-
-        let forward_xz = Vec3::new(forward.x, 0.0, forward.z).normalize_or_zero();
-        let right_xz = Vec3::new(right.x, 0.0, right.z).normalize_or_zero();
-
-        let mut translation = transform.translation;
-
-        // Apply camera movement based on movement flags
-        if movement_flags & MOVE_FORWARD != 0 {
-            translation += forward_xz * speed * dt;
-        }
-        if movement_flags & MOVE_BACKWARD != 0 {
-            translation -= forward_xz * speed * dt;
-        }
-        if movement_flags & MOVE_LEFT != 0 {
-            translation -= right_xz * speed * dt;
-        }
-        if movement_flags & MOVE_RIGHT != 0 {
-            translation += right_xz * speed * dt;
-        }
-
-        const SMOOTHNESS: f32 = 0.9;
-        transform.translation = transform.translation.lerp(translation, SMOOTHNESS);
-
-
-        // Apply Y-axis camera rotation
-        if y_rotation.abs() > f32::EPSILON {
-            transform.rotate_y(y_rotation * dt);
-        }
+fn rotate_cube(time: Res<Time>, mut query: Query<&mut Transform, With<Rotates>>) {
+    let dt = time.delta().as_secs_f32();
+    for mut transform in &mut query {
+        transform.rotate_y(1.0 * dt);
     }
 }
-
-